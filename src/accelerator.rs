@@ -0,0 +1,70 @@
+use std::sync::Arc;
+
+use serde::Deserialize;
+
+use crate::bvh_node::BVHNode;
+use crate::flat_bvh::{BuildStats, FlatBVH};
+use crate::hittable::{Hittable, HittableList, Intersection};
+use crate::ray::Ray;
+
+#[derive(Deserialize, Clone, Copy, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum AcceleratorKind {
+    List,
+    #[default]
+    Bvh,
+    Sah,
+}
+
+pub enum Accelerator {
+    List(HittableList),
+    Bvh(BVHNode),
+    Sah {
+        objects: Vec<Arc<Hittable>>,
+        bvh: FlatBVH,
+    },
+}
+
+impl Accelerator {
+    pub fn new(kind: AcceleratorKind, list: HittableList, time0: f64, time1: f64) -> Self {
+        match kind {
+            AcceleratorKind::List => Self::List(list),
+            AcceleratorKind::Bvh => Self::Bvh(BVHNode::new(list, time0, time1)),
+            AcceleratorKind::Sah => {
+                let bounds: Vec<_> = list
+                    .objects
+                    .iter()
+                    .map(|object| {
+                        object
+                            .bounding_box(time0, time1)
+                            .expect("No bounding box in FlatBVH constructor")
+                    })
+                    .collect();
+
+                Self::Sah {
+                    objects: list.objects,
+                    bvh: FlatBVH::new(&bounds),
+                }
+            }
+        }
+    }
+
+    pub fn stats(&self) -> Option<&BuildStats> {
+        match self {
+            Self::Sah { bvh, .. } => Some(bvh.stats()),
+            _ => None,
+        }
+    }
+
+    pub fn hit(&self, ray: Ray, t_min: f64, t_max: f64) -> Option<Intersection<'_>> {
+        match self {
+            Self::List(list) => list.hit(ray, t_min, t_max),
+            Self::Bvh(node) => node.hit(ray, t_min, t_max),
+            Self::Sah { objects, bvh } => bvh.hit(ray, t_min, t_max, |i, ray, t_min, t_max| {
+                let mut intersection = objects[i].hit(ray, t_min, t_max)?;
+                intersection.object = Some(&objects[i]);
+                Some(intersection)
+            }),
+        }
+    }
+}
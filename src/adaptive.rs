@@ -0,0 +1,87 @@
+use serde::Deserialize;
+
+use crate::math::srgb_to_linear;
+use crate::vec3::Color;
+
+// Adaptive sampling, `sample_per_pixel` becomes the maximum sample count of a pixel
+#[derive(Deserialize, Clone, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct AdaptiveSampling {
+    #[serde(default = "default_min_samples")]
+    pub min_samples: u32,
+    // Samples taken between two error estimates
+    #[serde(default = "default_step")]
+    pub step: u32,
+    // Pixels stop being sampled once the standard error of their luminance relative to the
+    // luminance is below the threshold
+    #[serde(default = "default_threshold")]
+    pub threshold: f64,
+    // Image of the sample count of every pixel
+    #[serde(default)]
+    pub heatmap: Option<String>,
+}
+
+fn default_min_samples() -> u32 {
+    16
+}
+
+fn default_step() -> u32 {
+    8
+}
+
+fn default_threshold() -> f64 {
+    0.01
+}
+
+// Running mean and variance of the sample luminances of a pixel (Welford's algorithm)
+#[derive(Default)]
+pub struct PixelEstimator {
+    count: u32,
+    mean: f64,
+    m2: f64,
+}
+
+impl PixelEstimator {
+    pub fn add(&mut self, value: f64) {
+        self.count += 1;
+        let delta = value - self.mean;
+        self.mean += delta / self.count as f64;
+        self.m2 += delta * (value - self.mean);
+    }
+
+    // Standard error of the mean relative to the mean. Dark pixels are compared to a small
+    // floor so noise in black areas doesn't keep them sampled forever.
+    pub fn relative_error(&self) -> f64 {
+        if self.count < 2 {
+            return f64::INFINITY;
+        }
+
+        let variance = self.m2 / (self.count - 1) as f64;
+        let standard_error = (variance / self.count as f64).sqrt();
+        standard_error / self.mean.max(0.01)
+    }
+}
+
+// Linear color of a sample count on a black, purple, orange, yellow gradient
+pub fn heatmap_color(sample_count: u32, max_samples: u32) -> Color {
+    const STOPS: [(f64, f64, f64); 5] = [
+        (0.0, 0.0, 0.0),
+        (0.34, 0.06, 0.43),
+        (0.87, 0.27, 0.23),
+        (0.99, 0.75, 0.15),
+        (0.99, 1.0, 0.64),
+    ];
+
+    let t = sample_count as f64 / std::cmp::max(max_samples, 1) as f64;
+    let position = t.clamp(0.0, 1.0) * (STOPS.len() - 1) as f64;
+    let index = std::cmp::min(position as usize, STOPS.len() - 2);
+    let f = position - index as f64;
+    let (a, b) = (STOPS[index], STOPS[index + 1]);
+
+    // The stops are display colors
+    Color::new(
+        srgb_to_linear(a.0 + f * (b.0 - a.0)),
+        srgb_to_linear(a.1 + f * (b.1 - a.1)),
+        srgb_to_linear(a.2 + f * (b.2 - a.2)),
+    )
+}
@@ -0,0 +1,107 @@
+use serde::Deserialize;
+
+use crate::output::{OutputFormat, OutputSettings};
+use crate::vec3::Color;
+
+// Extra per pixel values written alongside the image. The lighting passes split the light
+// reflected by the first surface seen by its first bounce: light arriving straight from a
+// light or the background is direct, the rest is indirect. With the emission seen directly
+// they add up to the image.
+#[derive(Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum Aov {
+    // Reflectance of the first surface
+    Albedo,
+    // Shading normal of the first surface, in world space
+    Normal,
+    // Distance from the camera to the first surface
+    Depth,
+    // World space position of the first surface
+    Position,
+    // Index of the scene object first hit, starting at 1, 0 where nothing is hit
+    ObjectId,
+    DirectDiffuse,
+    IndirectDiffuse,
+    DirectSpecular,
+    IndirectSpecular,
+}
+
+pub const AOV_COUNT: usize = 9;
+
+impl Aov {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Albedo => "albedo",
+            Self::Normal => "normal",
+            Self::Depth => "depth",
+            Self::Position => "position",
+            Self::ObjectId => "object_id",
+            Self::DirectDiffuse => "direct_diffuse",
+            Self::IndirectDiffuse => "indirect_diffuse",
+            Self::DirectSpecular => "direct_specular",
+            Self::IndirectSpecular => "indirect_specular",
+        }
+    }
+
+    // Whether the layer goes through the pixel filter like the image. Geometric values can't be
+    // blended across edges, these layers keep the first sample of each pixel instead.
+    pub fn is_filtered(&self) -> bool {
+        !matches!(
+            self,
+            Self::Normal | Self::Depth | Self::Position | Self::ObjectId
+        )
+    }
+}
+
+// How the AOVs are saved, EXR images can hold them as layers, they are saved as separate files
+// for the other formats
+#[derive(Deserialize, Clone, Copy, Debug, Default, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum AovOutput {
+    #[default]
+    Layers,
+    Files,
+}
+
+// Values of every AOV for one sample, filled by the integrator
+#[derive(Clone, Copy)]
+pub struct AovValues {
+    values: [Color; AOV_COUNT],
+}
+
+impl Default for AovValues {
+    fn default() -> Self {
+        Self {
+            values: [Color::zero(); AOV_COUNT],
+        }
+    }
+}
+
+impl AovValues {
+    pub fn get(&self, aov: Aov) -> Color {
+        self.values[aov as usize]
+    }
+
+    pub fn set(&mut self, aov: Aov, value: Color) {
+        self.values[aov as usize] = value;
+    }
+
+    pub fn add(&mut self, aov: Aov, value: Color) {
+        self.values[aov as usize] += value;
+    }
+}
+
+// Files the AOVs are saved to next to the image, none when they are layers of the image
+pub fn aov_files(
+    settings: &OutputSettings,
+    aovs: &[Aov],
+    output: AovOutput,
+) -> Vec<(Aov, OutputSettings)> {
+    if output == AovOutput::Layers && settings.format == OutputFormat::Exr {
+        return Vec::new();
+    }
+
+    aovs.iter()
+        .map(|&aov| (aov, settings.with_suffix(aov.name())))
+        .collect()
+}
@@ -1,7 +1,8 @@
 use crate::ray::Ray;
+use crate::traversal;
 use crate::vec3::Vec3;
 
-#[derive(Clone)]
+#[derive(Clone, Copy)]
 pub struct AxisAlignedBB {
     min: Vec3,
     max: Vec3,
@@ -13,6 +14,17 @@ impl AxisAlignedBB {
         Self { min, max }
     }
 
+    pub fn empty() -> Self {
+        Self::new(
+            Vec3::new(f64::INFINITY, f64::INFINITY, f64::INFINITY),
+            Vec3::new(f64::NEG_INFINITY, f64::NEG_INFINITY, f64::NEG_INFINITY),
+        )
+    }
+
+    pub fn from_point(point: Vec3) -> Self {
+        Self::new(point, point)
+    }
+
     pub fn min(&self) -> Vec3 {
         self.min
     }
@@ -22,6 +34,7 @@ impl AxisAlignedBB {
     }
 
     pub fn hit(&self, ray: Ray, t_min: f64, t_max: f64) -> bool {
+        traversal::count_test();
         for i in 0..3 {
             let inv_div = 1.0 / ray.dir[i];
             let mut t0 = (self.min[i] - ray.origin[i]) * inv_div;
@@ -42,6 +55,54 @@ impl AxisAlignedBB {
         true
     }
 
+    pub fn pad(&self, min_extent: f64) -> Self {
+        let mut min = self.min;
+        let mut max = self.max;
+        let extent = self.extent();
+        if extent.x < min_extent {
+            min.x -= min_extent / 2.0;
+            max.x += min_extent / 2.0;
+        }
+        if extent.y < min_extent {
+            min.y -= min_extent / 2.0;
+            max.y += min_extent / 2.0;
+        }
+        if extent.z < min_extent {
+            min.z -= min_extent / 2.0;
+            max.z += min_extent / 2.0;
+        }
+
+        Self { min, max }
+    }
+
+    pub fn centroid(&self) -> Vec3 {
+        0.5 * (self.min + self.max)
+    }
+
+    pub fn extent(&self) -> Vec3 {
+        self.max - self.min
+    }
+
+    pub fn longest_axis(&self) -> usize {
+        let extent = self.extent();
+        if extent.x > extent.y && extent.x > extent.z {
+            0
+        } else if extent.y > extent.z {
+            1
+        } else {
+            2
+        }
+    }
+
+    pub fn surface_area(&self) -> f64 {
+        let extent = self.extent();
+        if extent.x < 0.0 || extent.y < 0.0 || extent.z < 0.0 {
+            return 0.0;
+        }
+
+        2.0 * (extent.x * extent.y + extent.y * extent.z + extent.z * extent.x)
+    }
+
     pub fn surrounding_box(bb0: &Self, bb1: &Self) -> Self {
         let small = Vec3::new(
             bb0.min().x.min(bb1.min().x),
@@ -1,11 +1,12 @@
-use rand::{thread_rng, Rng};
+use std::sync::Arc;
+
+use rand::Rng;
 
 use crate::axis_aligned_bb::AxisAlignedBB;
-use crate::hittable::{HittableList, Intersection, Hittable};
+use crate::hittable::{Hittable, HittableList, Intersection};
 use crate::ray::Ray;
-use crate::vec3::Vec3;
+use crate::rng::Pcg32;
 
-#[derive(Clone)]
 pub struct BVHNode {
     left: Option<Box<BVHNode>>,
     right: Option<Box<BVHNode>>,
@@ -14,64 +15,70 @@ pub struct BVHNode {
 }
 
 impl BVHNode {
-    pub fn new(
-        list: HittableList,
-        start: usize,
-        end: usize,
-        time0: f64,
-        time1: f64,
-        last_leaf: bool,
-    ) -> Self {
-        let axis = thread_rng().gen_range(0, 3);
-        let fn_comp = |a: &Hittable, b: &Hittable, axis| a.min()[axis] < b.min()[axis];
-
-        let object_span = end - start;
+    pub fn new(list: HittableList, time0: f64, time1: f64) -> Self {
+        // Fixed seed so the tree is the same on every run
+        let mut rng = Pcg32::new(0, 0);
+        let mut objects = list.objects;
+        Self::build(&mut objects, time0, time1, &mut rng)
+    }
 
-        let mut left;
-        let mut right;
-        if object_span == 1 {
-            left = Self::new(HittableList::new(), 0, 0, time0, time1, true);
-            right = left.clone();
-        } else if object_span == 2 {
-            if fn_comp(list.objects[start], list.objects[start + 1]) {
-                left = Self::new(HittableList::new(), 0, 0, time0, time1, true);
+    fn build(objects: &mut [Arc<Hittable>], time0: f64, time1: f64, rng: &mut Pcg32) -> Self {
+        if objects.len() <= 1 {
+            let mut data = HittableList::new();
+            for object in objects.iter() {
+                data.add(object.clone());
             }
-        } else {
+
+            let bb = data.bounding_box(time0, time1).unwrap_or_default();
+            return Self {
+                left: None,
+                right: None,
+                data,
+                bb,
+            };
         }
 
-        right
-    }
+        let axis = rng.gen_range(0..3);
+        objects.sort_by(|a, b| {
+            let a_min = bounding_box(a, time0, time1).min()[axis];
+            let b_min = bounding_box(b, time0, time1).min()[axis];
+            a_min
+                .partial_cmp(&b_min)
+                .unwrap_or(std::cmp::Ordering::Equal)
+        });
+
+        let mid = objects.len() / 2;
+        let (left_objects, right_objects) = objects.split_at_mut(mid);
+        let left = Self::build(left_objects, time0, time1, rng);
+        let right = Self::build(right_objects, time0, time1, rng);
+        let bb = AxisAlignedBB::surrounding_box(&left.bb, &right.bb);
 
-    pub fn new_uninit() -> Self {
         Self {
-            left: None,
-            right: None,
+            left: Some(Box::new(left)),
+            right: Some(Box::new(right)),
             data: HittableList::new(),
-            bb: AxisAlignedBB::new(Vec3::new(0.0, 0.0, 0.0), Vec3::new(0.0, 0.0, 0.0)),
+            bb,
         }
     }
 
-    pub fn hit(&self, ray: Ray, t_min: f64, t_max: f64) -> Option<Intersection> {
+    pub fn hit(&self, ray: Ray, t_min: f64, t_max: f64) -> Option<Intersection<'_>> {
         if !self.bb.hit(ray, t_min, t_max) {
             return None;
         }
-        
-        if !self.data.is_empty() {
-            return self.data.hit(ray, t_min, t_max);
-        }
 
-        if let Some(hit_left) = self.left.hit(ray, t_min, t_max) {
-            return Some(hit_left);
-        }
-
-        if let Some(hit_right) = self.right.hit(ray, t_min, t_max) {
-            return Some(hit_right);
+        match (&self.left, &self.right) {
+            (Some(left), Some(right)) => {
+                let hit_left = left.hit(ray, t_min, t_max);
+                let closest = hit_left.as_ref().map_or(t_max, |hit| hit.t);
+                right.hit(ray, t_min, closest).or(hit_left)
+            }
+            _ => self.data.hit(ray, t_min, t_max),
         }
-
-        None
     }
+}
 
-    pub fn bounding_box(&self) -> AxisAlignedBB {
-        self.bb.clone()
-    }
+fn bounding_box(object: &Hittable, time0: f64, time1: f64) -> AxisAlignedBB {
+    object
+        .bounding_box(time0, time1)
+        .expect("No bounding box in BVHNode constructor")
 }
@@ -0,0 +1,185 @@
+use serde::Deserialize;
+
+// Pixel reconstruction filters. Every sample is added to the pixels whose center is within
+// `radius` of it, weighted by the filter value at the offset from the pixel center.
+#[derive(Deserialize, Clone, Copy, Debug)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+pub enum Filter {
+    Box {
+        #[serde(default = "default_box_radius")]
+        radius: f64,
+    },
+    Tent {
+        #[serde(default = "default_tent_radius")]
+        radius: f64,
+    },
+    Gaussian {
+        #[serde(default = "default_gaussian_radius")]
+        radius: f64,
+        #[serde(default = "default_sigma")]
+        sigma: f64,
+    },
+    // Mitchell-Netravali cubic, b = c = 1/3 is their recommended trade off between blurring and
+    // ringing
+    Mitchell {
+        #[serde(default = "default_mitchell_radius")]
+        radius: f64,
+        #[serde(default = "default_mitchell_parameter")]
+        b: f64,
+        #[serde(default = "default_mitchell_parameter")]
+        c: f64,
+    },
+    // Sinc windowed by a sinc stretched to the radius
+    Lanczos {
+        #[serde(default = "default_lanczos_radius")]
+        radius: f64,
+    },
+}
+
+fn default_box_radius() -> f64 {
+    0.5
+}
+
+fn default_tent_radius() -> f64 {
+    1.0
+}
+
+fn default_gaussian_radius() -> f64 {
+    1.5
+}
+
+fn default_sigma() -> f64 {
+    0.5
+}
+
+fn default_mitchell_radius() -> f64 {
+    2.0
+}
+
+fn default_mitchell_parameter() -> f64 {
+    1.0 / 3.0
+}
+
+fn default_lanczos_radius() -> f64 {
+    3.0
+}
+
+impl Default for Filter {
+    fn default() -> Self {
+        Self::Box {
+            radius: default_box_radius(),
+        }
+    }
+}
+
+impl Filter {
+    pub fn radius(&self) -> f64 {
+        match *self {
+            Self::Box { radius }
+            | Self::Tent { radius }
+            | Self::Gaussian { radius, .. }
+            | Self::Mitchell { radius, .. }
+            | Self::Lanczos { radius } => radius,
+        }
+    }
+
+    // Filters without a positive radius or sigma give no weight to any pixel
+    pub fn validate(&self) -> Result<(), String> {
+        if self.radius() <= 0.0 {
+            return Err(format!(
+                "Filter radius must be positive (got {})",
+                self.radius()
+            ));
+        }
+        if let Self::Gaussian { sigma, .. } = *self {
+            if sigma <= 0.0 {
+                return Err(format!(
+                    "Gaussian filter sigma must be positive (got {})",
+                    sigma
+                ));
+            }
+        }
+
+        Ok(())
+    }
+
+    // Number of pixels around its own pixel a sample can reach
+    pub fn margin(&self) -> usize {
+        (self.radius() - 0.5).ceil().max(0.0) as usize
+    }
+
+    // Separable filters, the weight is the product of the 1D filter on both axes
+    pub fn evaluate(&self, dx: f64, dy: f64) -> f64 {
+        self.evaluate_1d(dx) * self.evaluate_1d(dy)
+    }
+
+    fn evaluate_1d(&self, x: f64) -> f64 {
+        let x = x.abs();
+        match *self {
+            Self::Box { radius } => {
+                if x <= radius {
+                    1.0
+                } else {
+                    0.0
+                }
+            }
+            Self::Tent { radius } => (radius - x).max(0.0),
+            Self::Gaussian { radius, sigma } => {
+                let gaussian = |x: f64| (-x * x / (2.0 * sigma * sigma)).exp();
+                // Shifted so the filter reaches 0 at the radius
+                (gaussian(x) - gaussian(radius)).max(0.0)
+            }
+            Self::Mitchell { radius, b, c } => mitchell(2.0 * x / radius, b, c),
+            Self::Lanczos { radius } => {
+                if x >= radius {
+                    0.0
+                } else {
+                    sinc(x) * sinc(x / radius)
+                }
+            }
+        }
+    }
+}
+
+// Cubic over [0, 2]
+fn mitchell(x: f64, b: f64, c: f64) -> f64 {
+    if x >= 2.0 {
+        0.0
+    } else if x >= 1.0 {
+        ((-b - 6.0 * c) * x * x * x
+            + (6.0 * b + 30.0 * c) * x * x
+            + (-12.0 * b - 48.0 * c) * x
+            + (8.0 * b + 24.0 * c))
+            / 6.0
+    } else {
+        ((12.0 - 9.0 * b - 6.0 * c) * x * x * x
+            + (-18.0 + 12.0 * b + 6.0 * c) * x * x
+            + (6.0 - 2.0 * b))
+            / 6.0
+    }
+}
+
+fn sinc(x: f64) -> f64 {
+    if x < 1e-5 {
+        return 1.0;
+    }
+    let x = std::f64::consts::PI * x;
+    x.sin() / x
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Filter;
+
+    #[test]
+    fn rejects_empty_filters() {
+        assert!(Filter::default().validate().is_ok());
+        assert!(Filter::Tent { radius: 0.0 }.validate().is_err());
+        assert!(Filter::Lanczos { radius: -1.0 }.validate().is_err());
+        let gaussian = Filter::Gaussian {
+            radius: 1.5,
+            sigma: 0.0,
+        };
+        assert!(gaussian.validate().is_err());
+    }
+}
@@ -0,0 +1,289 @@
+use std::fmt;
+use std::time::{Duration, Instant};
+
+use crate::axis_aligned_bb::AxisAlignedBB;
+use crate::hittable::Intersection;
+use crate::ray::Ray;
+use crate::vec3::Vec3;
+
+const BIN_COUNT: usize = 12;
+const MAX_LEAF_SIZE: usize = 4;
+const TRAVERSAL_COST: f64 = 0.125;
+const STACK_SIZE: usize = 64;
+
+#[derive(Clone, Copy)]
+struct LinearNode {
+    bb: AxisAlignedBB,
+    // First primitive for a leaf, index of the second child for an interior node
+    offset: usize,
+    // Primitive count, 0 for an interior node
+    count: usize,
+    axis: usize,
+}
+
+#[derive(Clone, Copy)]
+struct Bin {
+    bb: AxisAlignedBB,
+    count: usize,
+}
+
+#[derive(Clone, Default)]
+pub struct BuildStats {
+    pub primitive_count: usize,
+    pub node_count: usize,
+    pub leaf_count: usize,
+    pub max_depth: usize,
+    pub min_leaf_size: usize,
+    pub max_leaf_size: usize,
+    pub build_time: Duration,
+}
+
+impl BuildStats {
+    pub fn avg_leaf_size(&self) -> f64 {
+        if self.leaf_count == 0 {
+            0.0
+        } else {
+            self.primitive_count as f64 / self.leaf_count as f64
+        }
+    }
+}
+
+impl fmt::Display for BuildStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} primitives, {} nodes, {} leaves, depth {}, leaf size {}..{} (avg {:.2}), built in {:.2?}",
+            self.primitive_count,
+            self.node_count,
+            self.leaf_count,
+            self.max_depth,
+            self.min_leaf_size,
+            self.max_leaf_size,
+            self.avg_leaf_size(),
+            self.build_time
+        )
+    }
+}
+
+// Bounding volume hierarchy built with the binned surface area heuristic and stored as a
+// depth first array. The first child of an interior node directly follows it in the array.
+// Primitives are referred to by their index in the bounding box slice given to `new`.
+pub struct FlatBVH {
+    nodes: Vec<LinearNode>,
+    indices: Vec<usize>,
+    stats: BuildStats,
+}
+
+impl FlatBVH {
+    pub fn new(bounds: &[AxisAlignedBB]) -> Self {
+        let start = Instant::now();
+
+        let mut bvh = Self {
+            nodes: Vec::with_capacity(2 * bounds.len()),
+            indices: (0..bounds.len()).collect(),
+            stats: BuildStats {
+                primitive_count: bounds.len(),
+                min_leaf_size: if bounds.is_empty() { 0 } else { usize::MAX },
+                ..Default::default()
+            },
+        };
+
+        let centroids: Vec<_> = bounds.iter().map(|bb| bb.centroid()).collect();
+        if !bounds.is_empty() {
+            bvh.build(bounds, &centroids, 0, bounds.len(), 1);
+        }
+
+        bvh.stats.node_count = bvh.nodes.len();
+        bvh.stats.build_time = start.elapsed();
+        bvh
+    }
+
+    pub fn stats(&self) -> &BuildStats {
+        &self.stats
+    }
+
+    fn build(
+        &mut self,
+        bounds: &[AxisAlignedBB],
+        centroids: &[Vec3],
+        start: usize,
+        end: usize,
+        depth: usize,
+    ) -> usize {
+        let node_index = self.nodes.len();
+        let count = end - start;
+
+        let mut bb = AxisAlignedBB::empty();
+        let mut centroid_bb = AxisAlignedBB::empty();
+        for &i in self.indices[start..end].iter() {
+            bb = AxisAlignedBB::surrounding_box(&bb, &bounds[i]);
+            centroid_bb = AxisAlignedBB::surrounding_box(
+                &centroid_bb,
+                &AxisAlignedBB::from_point(centroids[i]),
+            );
+        }
+
+        self.nodes.push(LinearNode {
+            bb,
+            offset: start,
+            count,
+            axis: 0,
+        });
+
+        let axis = centroid_bb.longest_axis();
+        let c_min = centroid_bb.min()[axis];
+        let c_extent = centroid_bb.max()[axis] - c_min;
+
+        // Traversal pushes one entry per interior node on the path, so the depth is capped
+        // at the size of its fixed stack
+        if count == 1 || c_extent <= 0.0 || depth >= STACK_SIZE {
+            self.make_leaf(depth, count);
+            return node_index;
+        }
+
+        let bin_of = |centroid: Vec3| {
+            let b = (BIN_COUNT as f64 * (centroid[axis] - c_min) / c_extent) as usize;
+            b.min(BIN_COUNT - 1)
+        };
+
+        let mut bins = [Bin {
+            bb: AxisAlignedBB::empty(),
+            count: 0,
+        }; BIN_COUNT];
+        for &i in self.indices[start..end].iter() {
+            let bin = &mut bins[bin_of(centroids[i])];
+            bin.count += 1;
+            bin.bb = AxisAlignedBB::surrounding_box(&bin.bb, &bounds[i]);
+        }
+
+        // Sweep from both sides to get the cost of splitting after each bin
+        let mut costs = [0.0; BIN_COUNT - 1];
+        let mut left_bb = AxisAlignedBB::empty();
+        let mut left_count = 0;
+        for (i, cost) in costs.iter_mut().enumerate() {
+            left_bb = AxisAlignedBB::surrounding_box(&left_bb, &bins[i].bb);
+            left_count += bins[i].count;
+            *cost = left_count as f64 * left_bb.surface_area();
+        }
+
+        let mut right_bb = AxisAlignedBB::empty();
+        let mut right_count = 0;
+        for i in (1..BIN_COUNT).rev() {
+            right_bb = AxisAlignedBB::surrounding_box(&right_bb, &bins[i].bb);
+            right_count += bins[i].count;
+            costs[i - 1] += right_count as f64 * right_bb.surface_area();
+        }
+
+        let (split_bin, min_cost) =
+            costs
+                .iter()
+                .enumerate()
+                .fold((0, f64::INFINITY), |best, (i, &cost)| {
+                    if cost < best.1 {
+                        (i, cost)
+                    } else {
+                        best
+                    }
+                });
+
+        let split_cost = TRAVERSAL_COST + min_cost / bb.surface_area();
+        let leaf_cost = count as f64;
+        if count <= MAX_LEAF_SIZE && split_cost >= leaf_cost {
+            self.make_leaf(depth, count);
+            return node_index;
+        }
+
+        let mut mid = start;
+        for i in start..end {
+            if bin_of(centroids[self.indices[i]]) <= split_bin {
+                self.indices.swap(i, mid);
+                mid += 1;
+            }
+        }
+
+        // Every centroid landed on the same side, fall back to an even split
+        if mid == start || mid == end {
+            mid = start + count / 2;
+            self.indices[start..end].sort_by(|&a, &b| {
+                centroids[a][axis]
+                    .partial_cmp(&centroids[b][axis])
+                    .unwrap_or(std::cmp::Ordering::Equal)
+            });
+        }
+
+        self.build(bounds, centroids, start, mid, depth + 1);
+        let second_child = self.build(bounds, centroids, mid, end, depth + 1);
+
+        let node = &mut self.nodes[node_index];
+        node.offset = second_child;
+        node.count = 0;
+        node.axis = axis;
+
+        node_index
+    }
+
+    fn make_leaf(&mut self, depth: usize, count: usize) {
+        self.stats.leaf_count += 1;
+        self.stats.max_depth = self.stats.max_depth.max(depth);
+        self.stats.min_leaf_size = self.stats.min_leaf_size.min(count);
+        self.stats.max_leaf_size = self.stats.max_leaf_size.max(count);
+    }
+
+    // Calls `hit_primitive(index, ray, t_min, closest)` for every primitive whose leaf is
+    // reached and returns the closest intersection. Children are visited front to back
+    // according to the sign of the ray direction on the split axis.
+    pub fn hit<'a, F>(
+        &self,
+        ray: Ray,
+        t_min: f64,
+        t_max: f64,
+        mut hit_primitive: F,
+    ) -> Option<Intersection<'a>>
+    where
+        F: FnMut(usize, Ray, f64, f64) -> Option<Intersection<'a>>,
+    {
+        if self.nodes.is_empty() {
+            return None;
+        }
+
+        let dir_is_neg = [ray.dir.x < 0.0, ray.dir.y < 0.0, ray.dir.z < 0.0];
+        let mut closest = t_max;
+        let mut intersection_out = None;
+
+        let mut stack = [0; STACK_SIZE];
+        let mut stack_len = 0;
+        let mut current = 0;
+
+        loop {
+            let node = &self.nodes[current];
+            if node.bb.hit(ray, t_min, closest) {
+                if node.count > 0 {
+                    for &i in self.indices[node.offset..node.offset + node.count].iter() {
+                        if let Some(hit) = hit_primitive(i, ray, t_min, closest) {
+                            closest = hit.t;
+                            intersection_out = Some(hit);
+                        }
+                    }
+                } else if dir_is_neg[node.axis] {
+                    stack[stack_len] = current + 1;
+                    stack_len += 1;
+                    current = node.offset;
+                    continue;
+                } else {
+                    stack[stack_len] = node.offset;
+                    stack_len += 1;
+                    current += 1;
+                    continue;
+                }
+            }
+
+            if stack_len == 0 {
+                break;
+            }
+            stack_len -= 1;
+            current = stack[stack_len];
+        }
+
+        intersection_out
+    }
+}
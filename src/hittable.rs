@@ -1,19 +1,28 @@
 pub mod hittable_list;
 pub mod intersection;
+pub mod mesh;
+pub mod triangle;
 
 pub use self::hittable_list::HittableList;
 pub use self::intersection::Intersection;
+pub use self::mesh::Mesh;
+
+use self::triangle::{triangle_bounding_box, triangle_hit};
+
+use std::sync::Arc;
 
 use crate::axis_aligned_bb::AxisAlignedBB;
 use crate::material::Material;
 use crate::ray::Ray;
-use crate::vec3::Vec3;
+use crate::transform::Transform;
+use crate::traversal;
+use crate::vec3::{coordinate_system, Vec3};
 
 pub enum Hittable {
     Sphere {
         center: Vec3,
         radius: f64,
-        material: Material,
+        material: Arc<Material>,
     },
     MovingSphere {
         center0: Vec3,
@@ -21,12 +30,27 @@ pub enum Hittable {
         time0: f64,
         time1: f64,
         radius: f64,
-        material: Material,
+        material: Arc<Material>,
+    },
+    Triangle {
+        v0: Vec3,
+        v1: Vec3,
+        v2: Vec3,
+        material: Arc<Material>,
+    },
+    Mesh {
+        mesh: Arc<Mesh>,
+    },
+    // Places `object` in the world with `transform`, optionally overriding its material
+    Instance {
+        object: Arc<Hittable>,
+        transform: Box<Transform>,
+        material: Option<Arc<Material>>,
     },
 }
 
 impl Hittable {
-    pub fn hit(&self, ray: Ray, t_min: f64, t_max: f64) -> Option<Intersection> {
+    pub fn hit(&self, ray: Ray, t_min: f64, t_max: f64) -> Option<Intersection<'_>> {
         match self {
             Self::Sphere {
                 center,
@@ -49,10 +73,45 @@ impl Hittable {
                 t_min,
                 t_max,
             ),
+            Self::Triangle {
+                v0,
+                v1,
+                v2,
+                material,
+            } => single_triangle_hit([*v0, *v1, *v2], material, ray, t_min, t_max),
+            Self::Mesh { mesh } => mesh.hit(ray, t_min, t_max),
+            Self::Instance {
+                object,
+                transform,
+                material,
+            } => instance_hit(object, transform, material.as_deref(), ray, t_min, t_max),
+        }
+    }
+
+    // Number of geometric primitives, triangles of meshes included
+    pub fn primitive_count(&self) -> usize {
+        match self {
+            Self::Mesh { mesh } => mesh.triangle_count(),
+            Self::Instance { object, .. } => object.primitive_count(),
+            _ => 1,
+        }
+    }
+
+    // Every material an intersection with the object can have
+    pub fn collect_materials<'a>(&'a self, materials: &mut Vec<&'a Material>) {
+        match self {
+            Self::Sphere { material, .. }
+            | Self::MovingSphere { material, .. }
+            | Self::Triangle { material, .. } => materials.push(material),
+            Self::Mesh { mesh } => materials.push(mesh.material()),
+            Self::Instance {
+                material: Some(material),
+                ..
+            } => materials.push(material),
+            Self::Instance { object, .. } => object.collect_materials(materials),
         }
     }
 
-    #[allow(dead_code)]
     pub fn bounding_box(&self, t0: f64, t1: f64) -> Option<AxisAlignedBB> {
         match self {
             Self::Sphere { center, radius, .. } => {
@@ -86,6 +145,13 @@ impl Hittable {
 
                 Some(AxisAlignedBB::surrounding_box(&bb0, &bb1))
             }
+            Self::Triangle { v0, v1, v2, .. } => Some(triangle_bounding_box([*v0, *v1, *v2])),
+            Self::Mesh { mesh } => Some(mesh.bounding_box()),
+            Self::Instance {
+                object, transform, ..
+            } => object
+                .bounding_box(t0, t1)
+                .map(|bb| transform.apply_bounding_box(&bb)),
         }
     }
 }
@@ -97,7 +163,8 @@ fn sphere_hit(
     ray: Ray,
     t_min: f64,
     t_max: f64,
-) -> Option<Intersection> {
+) -> Option<Intersection<'_>> {
+    traversal::count_test();
     let oc = ray.origin - center;
     let a = ray.dir.length_sq();
     let half_b = oc.dot(ray.dir);
@@ -114,12 +181,21 @@ fn sphere_hit(
                 let point = ray.at(t);
                 let outward_normal = ((point - center) / radius).normalize();
                 let (front_face, normal) = Intersection::get_face_normal(ray, outward_normal);
+                let (u, v, dpdu, dpdv) = sphere_uv(outward_normal, radius);
                 return Some(Intersection {
                     point,
                     normal,
+                    geometric_normal: outward_normal,
                     t,
+                    u,
+                    v,
+                    dpdu,
+                    dpdv,
                     front_face,
+                    vertex_color: None,
+                    barycentrics: None,
                     material,
+                    object: None,
                 });
             }
         }
@@ -131,12 +207,21 @@ fn sphere_hit(
                 let point = ray.at(t);
                 let outward_normal = ((point - center) / radius).normalize();
                 let (front_face, normal) = Intersection::get_face_normal(ray, outward_normal);
+                let (u, v, dpdu, dpdv) = sphere_uv(outward_normal, radius);
                 return Some(Intersection {
                     point,
                     normal,
+                    geometric_normal: outward_normal,
                     t,
+                    u,
+                    v,
+                    dpdu,
+                    dpdv,
                     front_face,
+                    vertex_color: None,
+                    barycentrics: None,
                     material,
+                    object: None,
                 });
             }
         }
@@ -153,9 +238,10 @@ fn moving_sphere_hit(
     ray: Ray,
     t_min: f64,
     t_max: f64,
-) -> Option<Intersection> {
+) -> Option<Intersection<'_>> {
     let (center0, center1) = centers;
     let (time0, time1) = times;
+    traversal::count_test();
 
     let oc = ray.origin - center(center0, center1, time0, time1, ray.time);
     let a = ray.dir.length_sq();
@@ -175,12 +261,21 @@ fn moving_sphere_hit(
                     / radius)
                     .normalize();
                 let (front_face, normal) = Intersection::get_face_normal(ray, outward_normal);
+                let (u, v, dpdu, dpdv) = sphere_uv(outward_normal, radius);
                 return Some(Intersection {
                     point,
                     normal,
+                    geometric_normal: outward_normal,
                     t,
+                    u,
+                    v,
+                    dpdu,
+                    dpdv,
                     front_face,
+                    vertex_color: None,
+                    barycentrics: None,
                     material,
+                    object: None,
                 });
             }
         }
@@ -194,12 +289,21 @@ fn moving_sphere_hit(
                     / radius)
                     .normalize();
                 let (front_face, normal) = Intersection::get_face_normal(ray, outward_normal);
+                let (u, v, dpdu, dpdv) = sphere_uv(outward_normal, radius);
                 return Some(Intersection {
                     point,
                     normal,
+                    geometric_normal: outward_normal,
                     t,
+                    u,
+                    v,
+                    dpdu,
+                    dpdv,
                     front_face,
+                    vertex_color: None,
+                    barycentrics: None,
                     material,
+                    object: None,
                 });
             }
         }
@@ -208,6 +312,85 @@ fn moving_sphere_hit(
     None
 }
 
+// Spherical mapping of a point on the unit sphere, u goes around the y axis starting
+// at -x and v goes from the bottom to the top
+fn sphere_uv(p: Vec3, radius: f64) -> (f64, f64, Vec3, Vec3) {
+    use std::f64::consts::PI;
+
+    let theta = (-p.y).acos();
+    let phi = (-p.z).atan2(p.x) + PI;
+
+    let dpdu = 2.0 * PI * radius * Vec3::new(p.z, 0.0, -p.x);
+    let sin_theta = (p.x * p.x + p.z * p.z).sqrt();
+    let dpdv = if sin_theta > 1e-8 {
+        PI * radius * Vec3::new(-p.x * p.y / sin_theta, sin_theta, -p.y * p.z / sin_theta)
+    } else {
+        // Tangents are undefined at the poles
+        coordinate_system(p).1
+    };
+
+    (phi / (2.0 * PI), theta / PI, dpdu, dpdv)
+}
+
+fn single_triangle_hit(
+    vertices: [Vec3; 3],
+    material: &Material,
+    ray: Ray,
+    t_min: f64,
+    t_max: f64,
+) -> Option<Intersection<'_>> {
+    let hit = triangle_hit(vertices, ray, t_min, t_max)?;
+    let b = hit.barycentrics;
+
+    let point = b[0] * vertices[0] + b[1] * vertices[1] + b[2] * vertices[2];
+    let outward_normal = (vertices[1] - vertices[0])
+        .cross(vertices[2] - vertices[0])
+        .normalize();
+    let (front_face, normal) = Intersection::get_face_normal(ray, outward_normal);
+    Some(Intersection {
+        point,
+        normal,
+        geometric_normal: outward_normal,
+        t: hit.t,
+        u: b[1],
+        v: b[2],
+        dpdu: vertices[1] - vertices[0],
+        dpdv: vertices[2] - vertices[0],
+        front_face,
+        vertex_color: None,
+        barycentrics: Some(b),
+        material,
+        object: None,
+    })
+}
+
+fn instance_hit<'a>(
+    object: &'a Hittable,
+    transform: &Transform,
+    material: Option<&'a Material>,
+    ray: Ray,
+    t_min: f64,
+    t_max: f64,
+) -> Option<Intersection<'a>> {
+    let local_ray = transform.apply_inverse_ray(ray);
+    let mut intersection = object.hit(local_ray, t_min, t_max)?;
+
+    // The inverse transpose keeps the normal on the same side of the surface so the
+    // front face flag computed in object space is still valid
+    intersection.point = transform.apply_point(intersection.point);
+    intersection.normal = transform.apply_normal(intersection.normal).normalize();
+    intersection.geometric_normal = transform
+        .apply_normal(intersection.geometric_normal)
+        .normalize();
+    intersection.dpdu = transform.apply_vector(intersection.dpdu);
+    intersection.dpdv = transform.apply_vector(intersection.dpdv);
+    if let Some(material) = material {
+        intersection.material = material;
+    }
+
+    Some(intersection)
+}
+
 fn center(center0: Vec3, center1: Vec3, t0: f64, t1: f64, time: f64) -> Vec3 {
     center0 + ((time - t0) / (t1 - t0)) * (center1 - center0)
 }
@@ -21,7 +21,7 @@ impl HittableList {
         self.objects.push(object);
     }
 
-    pub fn hit(&self, ray: Ray, t_min: f64, t_max: f64) -> Option<Intersection> {
+    pub fn hit(&self, ray: Ray, t_min: f64, t_max: f64) -> Option<Intersection<'_>> {
         let mut closest = t_max;
         let mut intersection_out = None;
 
@@ -29,8 +29,9 @@ impl HittableList {
             let intersection = object.hit(ray, t_min, closest);
 
             intersection_out = match intersection {
-                Some(hit_record) => {
+                Some(mut hit_record) => {
                     closest = hit_record.t;
+                    hit_record.object = Some(object);
                     Some(hit_record)
                 }
                 None => intersection_out,
@@ -40,7 +41,6 @@ impl HittableList {
         intersection_out
     }
 
-    #[allow(dead_code)]
     pub fn bounding_box(&self, t0: f64, t1: f64) -> Option<AxisAlignedBB> {
         if self.objects.is_empty() {
             return None;
@@ -1,13 +1,27 @@
+use super::Hittable;
 use crate::material::Material;
 use crate::ray::Ray;
-use crate::vec3::Vec3;
+use crate::vec3::{Color, Vec3};
 
 pub struct Intersection<'a> {
     pub point: Vec3,
+    // Shading normal, on the side the ray comes from
     pub normal: Vec3,
+    // Outward normal of the surface itself, without shading normals
+    pub geometric_normal: Vec3,
     pub t: f64,
+    pub u: f64,
+    pub v: f64,
+    // Partial derivatives of the point with respect to u and v
+    pub dpdu: Vec3,
+    pub dpdv: Vec3,
     pub front_face: bool,
+    pub vertex_color: Option<Color>,
+    // Barycentric coordinates of the point in the triangle that was hit
+    pub barycentrics: Option<[f64; 3]>,
     pub material: &'a Material,
+    // Object of the scene that was hit, set by the accelerator
+    pub object: Option<&'a Hittable>,
 }
 
 impl Intersection<'_> {
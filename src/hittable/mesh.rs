@@ -0,0 +1,204 @@
+use std::sync::Arc;
+
+use super::intersection::Intersection;
+use super::triangle::{triangle_bounding_box, triangle_hit};
+use crate::axis_aligned_bb::AxisAlignedBB;
+use crate::flat_bvh::FlatBVH;
+use crate::material::Material;
+use crate::ray::Ray;
+use crate::vec3::{coordinate_system, Color, Vec3};
+
+// Triangle mesh sharing its vertex attributes between faces. Normals, uvs and colors are
+// optional, when present there is one per position.
+pub struct Mesh {
+    positions: Vec<Vec3>,
+    normals: Vec<Vec3>,
+    uvs: Vec<(f64, f64)>,
+    colors: Vec<Color>,
+    indices: Vec<usize>,
+    material: Arc<Material>,
+    bvh: FlatBVH,
+    bb: AxisAlignedBB,
+}
+
+impl Mesh {
+    pub fn new(
+        positions: Vec<Vec3>,
+        normals: Vec<Vec3>,
+        uvs: Vec<(f64, f64)>,
+        colors: Vec<Color>,
+        indices: Vec<usize>,
+        material: Arc<Material>,
+    ) -> Result<Self, String> {
+        if !indices.len().is_multiple_of(3) {
+            return Err(format!(
+                "Mesh index count must be a multiple of 3 (got {})",
+                indices.len()
+            ));
+        }
+        if let Some(index) = indices.iter().find(|&&i| i >= positions.len()) {
+            return Err(format!(
+                "Mesh index {} is out of range ({} positions)",
+                index,
+                positions.len()
+            ));
+        }
+        if !normals.is_empty() && normals.len() != positions.len() {
+            return Err(format!(
+                "Mesh has {} normals for {} positions",
+                normals.len(),
+                positions.len()
+            ));
+        }
+        if !uvs.is_empty() && uvs.len() != positions.len() {
+            return Err(format!(
+                "Mesh has {} uvs for {} positions",
+                uvs.len(),
+                positions.len()
+            ));
+        }
+
+        if !colors.is_empty() && colors.len() != positions.len() {
+            return Err(format!(
+                "Mesh has {} colors for {} positions",
+                colors.len(),
+                positions.len()
+            ));
+        }
+
+        let bounds: Vec<_> = indices
+            .chunks(3)
+            .map(|face| {
+                triangle_bounding_box([positions[face[0]], positions[face[1]], positions[face[2]]])
+            })
+            .collect();
+        let bb = bounds.iter().fold(AxisAlignedBB::empty(), |bb, face_bb| {
+            AxisAlignedBB::surrounding_box(&bb, face_bb)
+        });
+        let bvh = FlatBVH::new(&bounds);
+
+        Ok(Self {
+            positions,
+            normals,
+            uvs,
+            colors,
+            indices,
+            material,
+            bvh,
+            bb,
+        })
+    }
+
+    pub fn triangle_count(&self) -> usize {
+        self.indices.len() / 3
+    }
+
+    pub fn bounding_box(&self) -> AxisAlignedBB {
+        self.bb
+    }
+
+    pub fn material(&self) -> &Material {
+        &self.material
+    }
+
+    pub fn triangles(&self) -> impl Iterator<Item = [Vec3; 3]> + '_ {
+        self.indices.chunks(3).map(move |face| {
+            [
+                self.positions[face[0]],
+                self.positions[face[1]],
+                self.positions[face[2]],
+            ]
+        })
+    }
+
+    pub fn hit(&self, ray: Ray, t_min: f64, t_max: f64) -> Option<Intersection<'_>> {
+        if self.triangle_count() == 0 {
+            return None;
+        }
+
+        self.bvh.hit(ray, t_min, t_max, |i, ray, t_min, t_max| {
+            self.hit_triangle(i, ray, t_min, t_max)
+        })
+    }
+
+    fn hit_triangle(
+        &self,
+        face: usize,
+        ray: Ray,
+        t_min: f64,
+        t_max: f64,
+    ) -> Option<Intersection<'_>> {
+        let i = [
+            self.indices[3 * face],
+            self.indices[3 * face + 1],
+            self.indices[3 * face + 2],
+        ];
+        let p = [
+            self.positions[i[0]],
+            self.positions[i[1]],
+            self.positions[i[2]],
+        ];
+        let hit = triangle_hit(p, ray, t_min, t_max)?;
+        let b = hit.barycentrics;
+
+        let point = b[0] * p[0] + b[1] * p[1] + b[2] * p[2];
+        let geometric_normal = (p[1] - p[0]).cross(p[2] - p[0]).normalize();
+        let (front_face, mut normal) = Intersection::get_face_normal(ray, geometric_normal);
+
+        if !self.normals.is_empty() {
+            let n = [self.normals[i[0]], self.normals[i[1]], self.normals[i[2]]];
+            let shading_normal = (b[0] * n[0] + b[1] * n[1] + b[2] * n[2]).normalize();
+            normal = if shading_normal.dot(normal) < 0.0 {
+                -shading_normal
+            } else {
+                shading_normal
+            };
+        }
+
+        // Meshes without uvs use (0, 0), (1, 0) and (0, 1) for the vertices
+        let uv = if self.uvs.is_empty() {
+            [(0.0, 0.0), (1.0, 0.0), (0.0, 1.0)]
+        } else {
+            [self.uvs[i[0]], self.uvs[i[1]], self.uvs[i[2]]]
+        };
+        let u = b[0] * uv[0].0 + b[1] * uv[1].0 + b[2] * uv[2].0;
+        let v = b[0] * uv[0].1 + b[1] * uv[1].1 + b[2] * uv[2].1;
+
+        let duv02 = (uv[0].0 - uv[2].0, uv[0].1 - uv[2].1);
+        let duv12 = (uv[1].0 - uv[2].0, uv[1].1 - uv[2].1);
+        let dp02 = p[0] - p[2];
+        let dp12 = p[1] - p[2];
+        let determinant = duv02.0 * duv12.1 - duv02.1 * duv12.0;
+        let (dpdu, dpdv) = if determinant.abs() < 1e-12 {
+            coordinate_system(geometric_normal)
+        } else {
+            (
+                (duv12.1 * dp02 - duv02.1 * dp12) / determinant,
+                (duv02.0 * dp12 - duv12.0 * dp02) / determinant,
+            )
+        };
+
+        let vertex_color = if self.colors.is_empty() {
+            None
+        } else {
+            let c = [self.colors[i[0]], self.colors[i[1]], self.colors[i[2]]];
+            Some(b[0] * c[0] + b[1] * c[1] + b[2] * c[2])
+        };
+
+        Some(Intersection {
+            point,
+            normal,
+            geometric_normal,
+            t: hit.t,
+            u,
+            v,
+            dpdu,
+            dpdv,
+            front_face,
+            vertex_color,
+            barycentrics: Some(b),
+            material: &self.material,
+            object: None,
+        })
+    }
+}
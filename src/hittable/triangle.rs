@@ -0,0 +1,91 @@
+use crate::axis_aligned_bb::AxisAlignedBB;
+use crate::ray::Ray;
+use crate::traversal;
+use crate::vec3::Vec3;
+
+pub struct TriangleHit {
+    pub t: f64,
+    pub barycentrics: [f64; 3],
+}
+
+// Watertight ray/triangle intersection (Woop, Benthin and Wald 2013). Edge functions are
+// evaluated in a ray aligned coordinate system so rays can't slip between triangles
+// sharing an edge.
+pub fn triangle_hit(vertices: [Vec3; 3], ray: Ray, t_min: f64, t_max: f64) -> Option<TriangleHit> {
+    traversal::count_test();
+    let abs_dir = Vec3::new(ray.dir.x.abs(), ray.dir.y.abs(), ray.dir.z.abs());
+    let kz = if abs_dir.x > abs_dir.y && abs_dir.x > abs_dir.z {
+        0
+    } else if abs_dir.y > abs_dir.z {
+        1
+    } else {
+        2
+    };
+    let mut kx = (kz + 1) % 3;
+    let mut ky = (kx + 1) % 3;
+    if ray.dir[kz] < 0.0 {
+        std::mem::swap(&mut kx, &mut ky);
+    }
+
+    let sx = ray.dir[kx] / ray.dir[kz];
+    let sy = ray.dir[ky] / ray.dir[kz];
+    let sz = 1.0 / ray.dir[kz];
+
+    let a = vertices[0] - ray.origin;
+    let b = vertices[1] - ray.origin;
+    let c = vertices[2] - ray.origin;
+
+    let ax = a[kx] - sx * a[kz];
+    let ay = a[ky] - sy * a[kz];
+    let bx = b[kx] - sx * b[kz];
+    let by = b[ky] - sy * b[kz];
+    let cx = c[kx] - sx * c[kz];
+    let cy = c[ky] - sy * c[kz];
+
+    let u = cx * by - cy * bx;
+    let v = ax * cy - ay * cx;
+    let w = bx * ay - by * ax;
+
+    if (u < 0.0 || v < 0.0 || w < 0.0) && (u > 0.0 || v > 0.0 || w > 0.0) {
+        return None;
+    }
+
+    let det = u + v + w;
+    if det == 0.0 {
+        return None;
+    }
+
+    let az = sz * a[kz];
+    let bz = sz * b[kz];
+    let cz = sz * c[kz];
+    let t = (u * az + v * bz + w * cz) / det;
+
+    if t <= t_min || t >= t_max {
+        return None;
+    }
+
+    Some(TriangleHit {
+        t,
+        barycentrics: [u / det, v / det, w / det],
+    })
+}
+
+pub fn triangle_bounding_box(vertices: [Vec3; 3]) -> AxisAlignedBB {
+    let mut min = vertices[0];
+    let mut max = vertices[0];
+    for vertex in vertices.iter().skip(1) {
+        min = Vec3::new(
+            min.x.min(vertex.x),
+            min.y.min(vertex.y),
+            min.z.min(vertex.z),
+        );
+        max = Vec3::new(
+            max.x.max(vertex.x),
+            max.y.max(vertex.y),
+            max.z.max(vertex.z),
+        );
+    }
+
+    // Axis aligned triangles would get a flat box that rays can't hit
+    AxisAlignedBB::new(min, max).pad(1e-4)
+}
@@ -0,0 +1,86 @@
+pub mod ao;
+pub mod debug;
+pub mod path;
+
+use std::sync::Arc;
+
+use serde::Deserialize;
+
+use crate::aov::AovValues;
+use crate::ray::Ray;
+use crate::render_options::RenderOptions;
+use crate::sampler::Sampler;
+use crate::scene::Scene;
+use crate::vec3::Color;
+
+pub use self::ao::AmbientOcclusionIntegrator;
+pub use self::debug::{DebugIntegrator, DebugView};
+pub use self::path::PathIntegrator;
+
+// Computes the value of a camera ray, radiance for the rendering integrators and a false color
+// for the debug views. Integrators are shared by the render threads. `aovs` is given when
+// AOVs are saved, only the path and direct integrators fill it.
+pub trait Integrator: Send + Sync {
+    fn li(&self, ray: Ray, sampler: &mut dyn Sampler, aovs: Option<&mut AovValues>) -> Color;
+}
+
+#[derive(Deserialize, Clone, Copy, Debug, Default)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+pub enum IntegratorKind {
+    // Full global illumination
+    #[default]
+    Path,
+    // Light reaching the first diffuse surface straight from the lights or the background,
+    // following specular bounces
+    Direct,
+    // Fraction of the cosine weighted hemisphere above the first hit that is unoccluded within
+    // `distance`
+    Ao {
+        #[serde(default = "default_ao_distance")]
+        distance: f64,
+    },
+    // Shading normal, mapped from [-1, 1] to [0, 1]
+    Normals,
+    GeometricNormals,
+    // Distance to the first hit, divided by `max_distance` when set
+    Depth {
+        #[serde(default)]
+        max_distance: Option<f64>,
+    },
+    Uv,
+    Barycentrics,
+    // Random color per material
+    MaterialId,
+    // Heatmap of the bounding box and primitive tests of the camera ray, `max_tests` is the top
+    // of the color scale
+    BvhCost {
+        #[serde(default = "default_max_tests")]
+        max_tests: u32,
+    },
+}
+
+fn default_ao_distance() -> f64 {
+    f64::INFINITY
+}
+
+fn default_max_tests() -> u32 {
+    200
+}
+
+impl IntegratorKind {
+    pub fn create(&self, scene: &Scene, options: &RenderOptions) -> Arc<dyn Integrator> {
+        let debug = |view| Arc::new(DebugIntegrator::new(scene, view));
+        match *self {
+            Self::Path => Arc::new(PathIntegrator::new(scene, options, false)),
+            Self::Direct => Arc::new(PathIntegrator::new(scene, options, true)),
+            Self::Ao { distance } => Arc::new(AmbientOcclusionIntegrator::new(scene, distance)),
+            Self::Normals => debug(DebugView::Normals),
+            Self::GeometricNormals => debug(DebugView::GeometricNormals),
+            Self::Depth { max_distance } => debug(DebugView::Depth { max_distance }),
+            Self::Uv => debug(DebugView::Uv),
+            Self::Barycentrics => debug(DebugView::Barycentrics),
+            Self::MaterialId => debug(DebugView::MaterialId),
+            Self::BvhCost { max_tests } => debug(DebugView::BvhCost { max_tests }),
+        }
+    }
+}
@@ -0,0 +1,48 @@
+use std::sync::Arc;
+
+use super::Integrator;
+use crate::accelerator::Accelerator;
+use crate::aov::AovValues;
+use crate::onb::Onb;
+use crate::ray::Ray;
+use crate::sampler::{sample_cosine_hemisphere, Sampler};
+use crate::scene::Scene;
+use crate::vec3::Color;
+
+// One cosine weighted occlusion ray per sample, rays missing the scene are white
+pub struct AmbientOcclusionIntegrator {
+    world: Arc<Accelerator>,
+    distance: f64,
+}
+
+impl AmbientOcclusionIntegrator {
+    pub fn new(scene: &Scene, distance: f64) -> Self {
+        Self {
+            world: scene.get_objects(),
+            distance,
+        }
+    }
+}
+
+impl Integrator for AmbientOcclusionIntegrator {
+    fn li(&self, ray: Ray, sampler: &mut dyn Sampler, _aovs: Option<&mut AovValues>) -> Color {
+        let u = sampler.get_2d();
+        let intersection = match self.world.hit(ray, 0.001, f64::INFINITY) {
+            Some(intersection) => intersection,
+            None => return Color::new(1.0, 1.0, 1.0),
+        };
+
+        let frame = Onb::from_normal(intersection.normal);
+        let dir = frame.to_world(sample_cosine_hemisphere(u));
+        let occlusion_ray = Ray::new(intersection.point, dir, ray.time);
+        if self
+            .world
+            .hit(occlusion_ray, 0.001, self.distance)
+            .is_some()
+        {
+            Color::zero()
+        } else {
+            Color::new(1.0, 1.0, 1.0)
+        }
+    }
+}
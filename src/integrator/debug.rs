@@ -0,0 +1,102 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use super::Integrator;
+use crate::accelerator::Accelerator;
+use crate::adaptive::heatmap_color;
+use crate::aov::AovValues;
+use crate::ray::Ray;
+use crate::sampler::Sampler;
+use crate::scene::Scene;
+use crate::traversal;
+use crate::vec3::{Color, Vec3};
+
+#[derive(Clone, Copy, Debug)]
+pub enum DebugView {
+    Normals,
+    GeometricNormals,
+    Depth { max_distance: Option<f64> },
+    Uv,
+    Barycentrics,
+    MaterialId,
+    BvhCost { max_tests: u32 },
+}
+
+// Shows a property of the first surface hit by the camera ray, black where nothing is hit
+pub struct DebugIntegrator {
+    world: Arc<Accelerator>,
+    material_ids: HashMap<usize, usize>,
+    view: DebugView,
+}
+
+impl DebugIntegrator {
+    pub fn new(scene: &Scene, view: DebugView) -> Self {
+        Self {
+            world: scene.get_objects(),
+            material_ids: scene.material_ids(),
+            view,
+        }
+    }
+}
+
+impl Integrator for DebugIntegrator {
+    fn li(&self, ray: Ray, _sampler: &mut dyn Sampler, _aovs: Option<&mut AovValues>) -> Color {
+        traversal::take_test_count();
+        let intersection = self.world.hit(ray, 0.001, f64::INFINITY);
+
+        if let DebugView::BvhCost { max_tests } = self.view {
+            let tests = traversal::take_test_count();
+            return heatmap_color(std::cmp::min(tests, u32::MAX as u64) as u32, max_tests);
+        }
+
+        let intersection = match intersection {
+            Some(intersection) => intersection,
+            None => return Color::zero(),
+        };
+
+        match self.view {
+            DebugView::Normals => normal_color(intersection.normal),
+            DebugView::GeometricNormals => normal_color(intersection.geometric_normal),
+            DebugView::Depth { max_distance } => {
+                let distance = intersection.t * ray.dir.length();
+                let value = match max_distance {
+                    Some(max_distance) => distance / max_distance,
+                    None => distance,
+                };
+                Color::new(value, value, value)
+            }
+            DebugView::Uv => Color::new(intersection.u, intersection.v, 0.0),
+            DebugView::Barycentrics => match intersection.barycentrics {
+                Some(b) => Color::new(b[0], b[1], b[2]),
+                None => Color::zero(),
+            },
+            DebugView::MaterialId => {
+                let address = intersection.material as *const _ as usize;
+                match self.material_ids.get(&address) {
+                    Some(&id) => id_color(id),
+                    None => Color::zero(),
+                }
+            }
+            DebugView::BvhCost { .. } => unreachable!(),
+        }
+    }
+}
+
+fn normal_color(normal: Vec3) -> Color {
+    0.5 * (normal + Vec3::new(1.0, 1.0, 1.0))
+}
+
+// Saturated color with hues spread by the golden ratio so consecutive ids look different
+fn id_color(id: usize) -> Color {
+    let hue = (id as f64 * 0.618_033_988_749_895).fract() * 6.0;
+    let x = 1.0 - (hue % 2.0 - 1.0).abs();
+    let (r, g, b) = match hue as usize {
+        0 => (1.0, x, 0.0),
+        1 => (x, 1.0, 0.0),
+        2 => (0.0, 1.0, x),
+        3 => (0.0, x, 1.0),
+        4 => (x, 0.0, 1.0),
+        _ => (1.0, 0.0, x),
+    };
+    Color::new(0.1 + 0.9 * r, 0.1 + 0.9 * g, 0.1 + 0.9 * b)
+}
@@ -0,0 +1,210 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use super::Integrator;
+use crate::accelerator::Accelerator;
+use crate::aov::{Aov, AovValues};
+use crate::hittable::Intersection;
+use crate::light::{power_heuristic, LightList};
+use crate::onb::Onb;
+use crate::ray::Ray;
+use crate::render_options::RenderOptions;
+use crate::sampler::Sampler;
+use crate::scene::{Background, Scene};
+use crate::vec3::{Color, Vec3};
+
+// Path tracer sampling the lights at every diffuse bounce. With `direct_only` paths end at the
+// first diffuse surface, after looking for light straight from it.
+pub struct PathIntegrator {
+    world: Arc<Accelerator>,
+    lights: Arc<LightList>,
+    object_ids: HashMap<usize, usize>,
+    background: Background,
+    max_depth: u32,
+    roulette_depth: u32,
+    direct_only: bool,
+}
+
+impl PathIntegrator {
+    pub fn new(scene: &Scene, options: &RenderOptions, direct_only: bool) -> Self {
+        Self {
+            world: scene.get_objects(),
+            lights: scene.get_lights(),
+            object_ids: scene.object_ids(),
+            background: options.background.clone(),
+            max_depth: options.max_depth,
+            roulette_depth: options.roulette_depth,
+            direct_only,
+        }
+    }
+}
+
+impl Integrator for PathIntegrator {
+    // Radiance arriving along `ray`, following the path it starts for up to `max_depth` bounces
+    fn li(
+        &self,
+        mut ray: Ray,
+        sampler: &mut dyn Sampler,
+        mut aovs: Option<&mut AovValues>,
+    ) -> Color {
+        let world: &Accelerator = &self.world;
+        let lights: &LightList = &self.lights;
+
+        let mut radiance = Color::zero();
+        let mut throughput = Color::new(1.0, 1.0, 1.0);
+        // Density of the scattering that produced `ray` when the lights could have been sampled
+        // from its origin too
+        let mut bsdf_pdf = None;
+        let mut after_diffuse = false;
+        // Whether the first surface scatters specularly, for the lighting AOVs
+        let mut first_specular = false;
+
+        for depth in 0..self.max_depth {
+            let intersection = match world.hit(ray, 0.001, f64::INFINITY) {
+                Some(intersection) => intersection,
+                None => {
+                    let background = throughput * self.background.get_color(ray.dir);
+                    radiance += background;
+                    add_light_aov(aovs.as_deref_mut(), first_specular, depth, background);
+                    break;
+                }
+            };
+
+            let material = intersection.material;
+            if depth == 0 {
+                first_specular = material.is_specular();
+                if let Some(aovs) = aovs.as_deref_mut() {
+                    self.set_surface_aovs(aovs, ray, &intersection);
+                }
+            }
+
+            let mut emitted = material.emitted(&intersection);
+            if let Some(bsdf_pdf) = bsdf_pdf {
+                if emitted != Color::zero() {
+                    emitted *= power_heuristic(bsdf_pdf, lights.pdf(ray, &intersection));
+                }
+            }
+            radiance += throughput * emitted;
+            add_light_aov(
+                aovs.as_deref_mut(),
+                first_specular,
+                depth,
+                throughput * emitted,
+            );
+
+            if self.direct_only && after_diffuse {
+                break;
+            }
+
+            // Drawn even when they aren't used to keep the dimensions of every bounce aligned
+            let u_light = sampler.get_2d();
+            let uc_light = sampler.get_1d();
+            let u = sampler.get_2d();
+            let uc = sampler.get_1d();
+            let u_roulette = sampler.get_1d();
+
+            let frame = Onb::from_normal(intersection.normal);
+            let sample_lights = !material.is_specular() && !lights.is_empty();
+            if sample_lights {
+                let direct = throughput
+                    * direct_light(ray, &intersection, &frame, world, lights, uc_light, u_light);
+                radiance += direct;
+                add_light_aov(aovs.as_deref_mut(), first_specular, depth + 1, direct);
+            }
+
+            let wo = frame.to_local(-ray.dir.normalize());
+            let sample = match material.sample(&intersection, wo, u, uc) {
+                Some(sample) if sample.pdf > 0.0 => sample,
+                _ => break,
+            };
+
+            throughput *= (sample.wi.z.abs() / sample.pdf) * sample.f;
+            bsdf_pdf = if sample_lights && !sample.specular {
+                Some(sample.pdf)
+            } else {
+                None
+            };
+            after_diffuse |= !sample.specular;
+            ray = Ray::new(intersection.point, frame.to_world(sample.wi), ray.time);
+
+            // Dim paths are terminated with a probability and the survivors are scaled up to
+            // compensate, which keeps the estimate unbiased
+            if depth + 1 >= self.roulette_depth {
+                let survival = throughput.x.max(throughput.y).max(throughput.z).min(1.0);
+                if u_roulette >= survival {
+                    break;
+                }
+                throughput /= survival;
+            }
+        }
+
+        radiance
+    }
+}
+
+impl PathIntegrator {
+    fn set_surface_aovs(&self, aovs: &mut AovValues, ray: Ray, intersection: &Intersection) {
+        let depth = intersection.t * ray.dir.length();
+        let object_id = intersection
+            .object
+            .and_then(|object| self.object_ids.get(&(object as *const _ as usize)))
+            .map_or(0.0, |&id| id as f64);
+
+        aovs.set(Aov::Albedo, intersection.material.albedo(intersection));
+        aovs.set(Aov::Normal, intersection.normal);
+        aovs.set(Aov::Depth, Vec3::new(depth, depth, depth));
+        aovs.set(Aov::Position, intersection.point);
+        aovs.set(Aov::ObjectId, Vec3::new(object_id, object_id, object_id));
+    }
+}
+
+// Adds light that reached the first surface after `bounces` bounces (0 for light seen directly)
+// to the matching lighting AOV
+fn add_light_aov(aovs: Option<&mut AovValues>, first_specular: bool, bounces: u32, light: Color) {
+    let aov = match (bounces, first_specular) {
+        (0, _) => return,
+        (1, false) => Aov::DirectDiffuse,
+        (1, true) => Aov::DirectSpecular,
+        (_, false) => Aov::IndirectDiffuse,
+        (_, true) => Aov::IndirectSpecular,
+    };
+    if let Some(aovs) = aovs {
+        aovs.add(aov, light);
+    }
+}
+
+// Light arriving at the intersection straight from a light, sampled toward a point on one of
+// the lights and weighted against the chance of scattering toward it
+fn direct_light(
+    ray: Ray,
+    intersection: &Intersection,
+    frame: &Onb,
+    world: &Accelerator,
+    lights: &LightList,
+    uc: f64,
+    u: (f64, f64),
+) -> Color {
+    let sample = match lights.sample(intersection.point, ray.time, uc, u) {
+        Some(sample) => sample,
+        None => return Color::zero(),
+    };
+
+    let to_light = sample.point - intersection.point;
+    let dist = to_light.length();
+    let dir = to_light / dist;
+    let wo = frame.to_local(-ray.dir.normalize());
+    let wi = frame.to_local(dir);
+    let f = intersection.material.eval(intersection, wo, wi);
+    if f == Color::zero() {
+        return Color::zero();
+    }
+
+    let shadow_ray = Ray::new(intersection.point, dir, ray.time);
+    if world.hit(shadow_ray, 0.001, dist * (1.0 - 1e-6)).is_some() {
+        return Color::zero();
+    }
+
+    let bsdf_pdf = intersection.material.pdf(wo, wi);
+    let weight = power_heuristic(sample.pdf, bsdf_pdf);
+    (weight * wi.z.abs() / sample.pdf) * f * sample.emission
+}
@@ -0,0 +1,309 @@
+use crate::hittable::{Hittable, HittableList, Intersection};
+use crate::material::Material;
+use crate::ray::Ray;
+use crate::sampler::sample_unit_sphere;
+use crate::transform::Transform;
+use crate::vec3::{luminance, Color, Vec3};
+
+enum LightShape {
+    Sphere {
+        center0: Vec3,
+        center1: Vec3,
+        time0: f64,
+        time1: f64,
+        radius: f64,
+    },
+    // `normal` is the outward normal, on the side the triangle emits
+    Triangle {
+        vertices: [Vec3; 3],
+        normal: Vec3,
+    },
+}
+
+struct Light {
+    shape: LightShape,
+    emission: Color,
+    area: f64,
+}
+
+pub struct LightSample {
+    pub point: Vec3,
+    pub emission: Color,
+    // Probability density of the direction toward `point`, in solid angle
+    pub pdf: f64,
+}
+
+// Every emissive primitive of the scene. Lights are picked proportionally to their power and
+// sampled uniformly over their area, so the density of a point only depends on the radiance
+// emitted there and can be found for a point reached by a scattered ray without knowing which
+// light it belongs to.
+pub struct LightList {
+    lights: Vec<Light>,
+    cdf: Vec<f64>,
+    total_power: f64,
+}
+
+impl LightList {
+    pub fn new(objects: &HittableList) -> Result<Self, String> {
+        let mut lights = Vec::new();
+        for object in objects.objects.iter() {
+            collect_lights(object, None, None, &mut lights)?;
+        }
+        lights.retain(|light| luminance(light.emission) > 0.0);
+
+        let mut cdf = Vec::with_capacity(lights.len());
+        let mut total_power = 0.0;
+        for light in lights.iter() {
+            total_power += luminance(light.emission) * light.area;
+            cdf.push(total_power);
+        }
+        for value in cdf.iter_mut() {
+            *value /= total_power;
+        }
+
+        Ok(Self {
+            lights,
+            cdf,
+            total_power,
+        })
+    }
+
+    pub fn len(&self) -> usize {
+        self.lights.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.lights.is_empty()
+    }
+
+    // Picks a light with `uc` and a point on it with `u`, None if the point doesn't emit
+    // toward `origin`
+    pub fn sample(&self, origin: Vec3, time: f64, uc: f64, u: (f64, f64)) -> Option<LightSample> {
+        if self.lights.is_empty() {
+            return None;
+        }
+
+        let index = std::cmp::min(
+            self.cdf.partition_point(|&value| value <= uc),
+            self.lights.len() - 1,
+        );
+        let light = &self.lights[index];
+        let (point, normal) = match light.shape {
+            LightShape::Sphere {
+                center0,
+                center1,
+                time0,
+                time1,
+                radius,
+            } => {
+                let center = if time1 > time0 {
+                    center0 + ((time - time0) / (time1 - time0)) * (center1 - center0)
+                } else {
+                    center0
+                };
+                let normal = sample_unit_sphere(u);
+                (center + radius * normal, normal)
+            }
+            LightShape::Triangle { vertices, normal } => {
+                let s = u.0.sqrt();
+                let (b1, b2) = (s * (1.0 - u.1), s * u.1);
+                let point = (1.0 - b1 - b2) * vertices[0] + b1 * vertices[1] + b2 * vertices[2];
+                (point, normal)
+            }
+        };
+
+        let to_light = point - origin;
+        let dist_sq = to_light.length_sq();
+        let cos_light = -to_light.dot(normal) / dist_sq.sqrt();
+        if cos_light <= 0.0 {
+            return None;
+        }
+
+        Some(LightSample {
+            point,
+            emission: light.emission,
+            pdf: self.area_pdf(light.emission) * dist_sq / cos_light,
+        })
+    }
+
+    // Density of sampling the emitter hit by `ray`, in solid angle
+    pub fn pdf(&self, ray: Ray, intersection: &Intersection) -> f64 {
+        let emission = match intersection.material.emission() {
+            Some(emission) => emission,
+            None => return 0.0,
+        };
+
+        let dist_sq = (intersection.point - ray.origin).length_sq();
+        // The geometric normal, as used when sampling triangles, so both densities agree on
+        // smooth shaded meshes
+        let cos_light = ray.dir.normalize().dot(intersection.geometric_normal).abs();
+        if cos_light == 0.0 {
+            return 0.0;
+        }
+
+        self.area_pdf(emission) * dist_sq / cos_light
+    }
+
+    fn area_pdf(&self, emission: Color) -> f64 {
+        if self.total_power > 0.0 {
+            luminance(emission) / self.total_power
+        } else {
+            0.0
+        }
+    }
+}
+
+// Weight of a sample from the strategy with density `pdf` when `other_pdf` could also have
+// produced it
+pub fn power_heuristic(pdf: f64, other_pdf: f64) -> f64 {
+    let (f, g) = (pdf * pdf, other_pdf * other_pdf);
+    if f + g == 0.0 {
+        0.0
+    } else {
+        f / (f + g)
+    }
+}
+
+fn collect_lights(
+    object: &Hittable,
+    transform: Option<&Transform>,
+    material: Option<&Material>,
+    lights: &mut Vec<Light>,
+) -> Result<(), String> {
+    let emission = |own: &Material| material.unwrap_or(own).emission();
+
+    match object {
+        Hittable::Sphere {
+            center,
+            radius,
+            material: own,
+        } => {
+            if let Some(emission) = emission(own) {
+                check_not_instanced(transform)?;
+                lights.push(sphere_light(
+                    *center,
+                    *center,
+                    (0.0, 0.0),
+                    *radius,
+                    emission,
+                ));
+            }
+        }
+        Hittable::MovingSphere {
+            center0,
+            center1,
+            time0,
+            time1,
+            radius,
+            material: own,
+        } => {
+            if let Some(emission) = emission(own) {
+                check_not_instanced(transform)?;
+                lights.push(sphere_light(
+                    *center0,
+                    *center1,
+                    (*time0, *time1),
+                    *radius,
+                    emission,
+                ));
+            }
+        }
+        Hittable::Triangle {
+            v0,
+            v1,
+            v2,
+            material: own,
+        } => {
+            if let Some(emission) = emission(own) {
+                lights.extend(triangle_light([*v0, *v1, *v2], transform, emission));
+            }
+        }
+        Hittable::Mesh { mesh } => {
+            if let Some(emission) = emission(mesh.material()) {
+                for vertices in mesh.triangles() {
+                    lights.extend(triangle_light(vertices, transform, emission));
+                }
+            }
+        }
+        Hittable::Instance {
+            object,
+            transform: instance_transform,
+            material: instance_material,
+        } => {
+            let transform = match transform {
+                Some(outer) => instance_transform.then(outer),
+                None => **instance_transform,
+            };
+            let material = material.or(instance_material.as_deref());
+            collect_lights(object, Some(&transform), material, lights)?;
+        }
+    }
+
+    Ok(())
+}
+
+// Sphere lights are sampled in world space, an instanced one would be hit by scattered rays
+// without ever being sampled
+fn check_not_instanced(transform: Option<&Transform>) -> Result<(), String> {
+    match transform {
+        Some(_) => Err("Emissive spheres can't be instanced".to_string()),
+        None => Ok(()),
+    }
+}
+
+fn sphere_light(
+    center0: Vec3,
+    center1: Vec3,
+    times: (f64, f64),
+    radius: f64,
+    emission: Color,
+) -> Light {
+    Light {
+        shape: LightShape::Sphere {
+            center0,
+            center1,
+            time0: times.0,
+            time1: times.1,
+            radius,
+        },
+        emission,
+        area: 4.0 * std::f64::consts::PI * radius * radius,
+    }
+}
+
+fn triangle_light(
+    vertices: [Vec3; 3],
+    transform: Option<&Transform>,
+    emission: Color,
+) -> Option<Light> {
+    let normal = (vertices[1] - vertices[0]).cross(vertices[2] - vertices[0]);
+    // The normal is transformed on its own to stay on the emitting side with mirroring transforms
+    let (vertices, normal) = match transform {
+        Some(transform) => (
+            [
+                transform.apply_point(vertices[0]),
+                transform.apply_point(vertices[1]),
+                transform.apply_point(vertices[2]),
+            ],
+            transform.apply_normal(normal),
+        ),
+        None => (vertices, normal),
+    };
+
+    let area = 0.5
+        * (vertices[1] - vertices[0])
+            .cross(vertices[2] - vertices[0])
+            .length();
+    if area == 0.0 {
+        return None;
+    }
+
+    Some(Light {
+        shape: LightShape::Triangle {
+            vertices,
+            normal: normal.normalize(),
+        },
+        emission,
+        area,
+    })
+}
@@ -0,0 +1,5 @@
+pub mod obj;
+pub mod ply;
+
+pub use self::obj::load_obj;
+pub use self::ply::load_ply;
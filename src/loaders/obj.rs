@@ -0,0 +1,322 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use crate::hittable::Mesh;
+use crate::material::Material;
+use crate::texture::Texture;
+use crate::vec3::{Color, Vec3};
+
+#[derive(Clone)]
+struct MtlMaterial {
+    kd: Color,
+    ks: Color,
+    ns: f64,
+    ni: f64,
+    d: f64,
+    illum: u32,
+    map_kd: Option<PathBuf>,
+}
+
+impl Default for MtlMaterial {
+    fn default() -> Self {
+        Self {
+            kd: Color::new(0.8, 0.8, 0.8),
+            ks: Color::zero(),
+            ns: 0.0,
+            ni: 1.5,
+            d: 1.0,
+            illum: 2,
+            map_kd: None,
+        }
+    }
+}
+
+impl MtlMaterial {
+    // Maps the Phong style MTL parameters onto the closest material we support
+    fn to_material(&self) -> Result<Material, String> {
+        let transparent = self.d < 1.0 || matches!(self.illum, 4 | 6 | 7 | 9);
+        let mirror = matches!(self.illum, 3 | 5)
+            || (self.ks.length_sq() > 0.0 && self.kd.length_sq() == 0.0);
+
+        let material = if transparent {
+            Material::Dielectric { ref_idx: self.ni }
+        } else if mirror {
+            Material::Metal {
+                albedo: Texture::solid(self.ks),
+                fuzz: (2.0 / (self.ns + 2.0)).sqrt().min(1.0),
+            }
+        } else {
+            let albedo = match &self.map_kd {
+                Some(path) => Arc::new(Texture::load_image(path)?),
+                None => Texture::solid(self.kd),
+            };
+            Material::Lambertian { albedo }
+        };
+
+        Ok(material)
+    }
+}
+
+// Vertex of a face as (position, uv, normal) indices
+type FaceVertex = (usize, Option<usize>, Option<usize>);
+
+#[derive(Default)]
+struct MeshBuilder {
+    vertices: HashMap<FaceVertex, usize>,
+    positions: Vec<Vec3>,
+    normals: Vec<Vec3>,
+    uvs: Vec<(f64, f64)>,
+    indices: Vec<usize>,
+    missing_normals: bool,
+    missing_uvs: bool,
+}
+
+impl MeshBuilder {
+    fn add_vertex(&mut self, vertex: FaceVertex, obj: &ObjData) -> usize {
+        if let Some(&index) = self.vertices.get(&vertex) {
+            return index;
+        }
+
+        let (p, uv, n) = vertex;
+        self.positions.push(obj.positions[p]);
+        match uv {
+            Some(uv) => self.uvs.push(obj.uvs[uv]),
+            None => self.missing_uvs = true,
+        }
+        match n {
+            Some(n) => self.normals.push(obj.normals[n]),
+            None => self.missing_normals = true,
+        }
+
+        let index = self.positions.len() - 1;
+        self.vertices.insert(vertex, index);
+        index
+    }
+
+    fn build(mut self, material: Arc<Material>) -> Result<Mesh, String> {
+        // Attributes are all or nothing for a mesh
+        if self.missing_normals {
+            self.normals.clear();
+        }
+        if self.missing_uvs {
+            self.uvs.clear();
+        }
+
+        Mesh::new(
+            self.positions,
+            self.normals,
+            self.uvs,
+            vec![],
+            self.indices,
+            material,
+        )
+    }
+}
+
+#[derive(Default)]
+struct ObjData {
+    positions: Vec<Vec3>,
+    normals: Vec<Vec3>,
+    uvs: Vec<(f64, f64)>,
+}
+
+// Loads every group/material pair of an OBJ file as a separate mesh with its material
+// from the MTL libraries
+pub fn load_obj(path: &Path) -> Result<Vec<Mesh>, String> {
+    let source = std::fs::read_to_string(path)
+        .map_err(|err| format!("Could not read \"{}\": {}", path.display(), err))?;
+    let dir = path.parent().unwrap_or_else(|| Path::new(""));
+
+    let mut obj = ObjData::default();
+    let mut mtl_materials: HashMap<String, Arc<Material>> = HashMap::new();
+    let default_material = Arc::new(MtlMaterial::default().to_material()?);
+
+    // Meshes in the order they were first used, keyed by (group, material)
+    let mut builders: Vec<((String, String), MeshBuilder)> = vec![];
+    let mut group = String::new();
+    let mut material_name = String::new();
+
+    for (line_index, line) in source.lines().enumerate() {
+        let error = |msg: &str| format!("{}:{}: {}", path.display(), line_index + 1, msg);
+
+        let line = line.split('#').next().unwrap_or("").trim();
+        let mut tokens = line.split_whitespace();
+        let keyword = match tokens.next() {
+            Some(keyword) => keyword,
+            None => continue,
+        };
+        let args: Vec<&str> = tokens.collect();
+
+        match keyword {
+            "v" => obj
+                .positions
+                .push(parse_vec3(&args).map_err(|e| error(&e))?),
+            "vn" => obj.normals.push(parse_vec3(&args).map_err(|e| error(&e))?),
+            "vt" => {
+                let u = parse_f64(args.first()).map_err(|e| error(&e))?;
+                let v = args.get(1).map_or(Ok(0.0), |v| parse_f64(Some(v)));
+                obj.uvs.push((u, v.map_err(|e| error(&e))?));
+            }
+            "g" | "o" => group = args.join(" "),
+            "usemtl" => material_name = args.join(" "),
+            "mtllib" => {
+                for lib in args.iter() {
+                    let lib_path = dir.join(lib);
+                    for (name, mtl) in load_mtl(&lib_path)? {
+                        mtl_materials.insert(name, Arc::new(mtl.to_material()?));
+                    }
+                }
+            }
+            "f" => {
+                if args.len() < 3 {
+                    return Err(error("Face with less than 3 vertices"));
+                }
+
+                let face = args
+                    .iter()
+                    .map(|arg| parse_face_vertex(arg, &obj))
+                    .collect::<Result<Vec<_>, _>>()
+                    .map_err(|e| error(&e))?;
+
+                let key = (group.clone(), material_name.clone());
+                let builder = match builders.iter().position(|(k, _)| *k == key) {
+                    Some(i) => &mut builders[i].1,
+                    None => {
+                        builders.push((key, MeshBuilder::default()));
+                        &mut builders.last_mut().unwrap().1
+                    }
+                };
+
+                let first = builder.add_vertex(face[0], &obj);
+                for i in 1..face.len() - 1 {
+                    let second = builder.add_vertex(face[i], &obj);
+                    let third = builder.add_vertex(face[i + 1], &obj);
+                    builder.indices.extend_from_slice(&[first, second, third]);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    builders
+        .into_iter()
+        .map(|((_, name), builder)| {
+            let material = mtl_materials
+                .get(&name)
+                .cloned()
+                .unwrap_or_else(|| default_material.clone());
+            builder.build(material)
+        })
+        .collect()
+}
+
+fn load_mtl(path: &Path) -> Result<HashMap<String, MtlMaterial>, String> {
+    let source = std::fs::read_to_string(path)
+        .map_err(|err| format!("Could not read \"{}\": {}", path.display(), err))?;
+
+    let dir = path.parent().unwrap_or_else(|| Path::new(""));
+    let mut materials = HashMap::new();
+    let mut current: Option<(String, MtlMaterial)> = None;
+
+    for (line_index, line) in source.lines().enumerate() {
+        let error = |msg: &str| format!("{}:{}: {}", path.display(), line_index + 1, msg);
+
+        let line = line.split('#').next().unwrap_or("").trim();
+        let mut tokens = line.split_whitespace();
+        let keyword = match tokens.next() {
+            Some(keyword) => keyword,
+            None => continue,
+        };
+        let args: Vec<&str> = tokens.collect();
+
+        if keyword == "newmtl" {
+            if let Some((name, mtl)) = current.take() {
+                materials.insert(name, mtl);
+            }
+            current = Some((args.join(" "), MtlMaterial::default()));
+            continue;
+        }
+
+        let mtl = match current.as_mut() {
+            Some((_, mtl)) => mtl,
+            None => continue,
+        };
+
+        match keyword {
+            "Kd" => mtl.kd = parse_vec3(&args).map_err(|e| error(&e))?,
+            "Ks" => mtl.ks = parse_vec3(&args).map_err(|e| error(&e))?,
+            "Ns" => mtl.ns = parse_f64(args.first()).map_err(|e| error(&e))?,
+            "Ni" => mtl.ni = parse_f64(args.first()).map_err(|e| error(&e))?,
+            "d" => mtl.d = parse_f64(args.first()).map_err(|e| error(&e))?,
+            // Texture options come before the file name
+            "map_Kd" => {
+                let file = args
+                    .last()
+                    .ok_or_else(|| error("Expected a texture path"))?;
+                mtl.map_kd = Some(dir.join(file));
+            }
+            "Tr" => mtl.d = 1.0 - parse_f64(args.first()).map_err(|e| error(&e))?,
+            "illum" => {
+                mtl.illum = args
+                    .first()
+                    .and_then(|arg| arg.parse().ok())
+                    .ok_or_else(|| error("Expected an illumination model"))?
+            }
+            _ => {}
+        }
+    }
+
+    if let Some((name, mtl)) = current {
+        materials.insert(name, mtl);
+    }
+
+    Ok(materials)
+}
+
+fn parse_f64(arg: Option<&&str>) -> Result<f64, String> {
+    let arg = arg.ok_or("Missing number")?;
+    arg.parse()
+        .map_err(|_| format!("Expected a number, got \"{}\"", arg))
+}
+
+fn parse_vec3(args: &[&str]) -> Result<Vec3, String> {
+    Ok(Vec3::new(
+        parse_f64(args.first())?,
+        parse_f64(args.get(1))?,
+        parse_f64(args.get(2))?,
+    ))
+}
+
+// OBJ indices start at 1, negative indices are relative to the end of the list
+fn parse_index(arg: &str, len: usize) -> Result<usize, String> {
+    let index: i64 = arg
+        .parse()
+        .map_err(|_| format!("Expected an index, got \"{}\"", arg))?;
+    let resolved = if index < 0 {
+        len as i64 + index
+    } else {
+        index - 1
+    };
+
+    if resolved < 0 || resolved >= len as i64 {
+        return Err(format!("Index {} is out of range", index));
+    }
+
+    Ok(resolved as usize)
+}
+
+fn parse_face_vertex(arg: &str, obj: &ObjData) -> Result<FaceVertex, String> {
+    let mut parts = arg.split('/');
+    let position = parse_index(parts.next().unwrap_or(""), obj.positions.len())?;
+    let uv = match parts.next() {
+        Some(uv) if !uv.is_empty() => Some(parse_index(uv, obj.uvs.len())?),
+        _ => None,
+    };
+    let normal = match parts.next() {
+        Some(n) if !n.is_empty() => Some(parse_index(n, obj.normals.len())?),
+        _ => None,
+    };
+
+    Ok((position, uv, normal))
+}
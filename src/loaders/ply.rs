@@ -0,0 +1,458 @@
+use std::convert::TryFrom;
+use std::path::Path;
+use std::sync::Arc;
+
+use crate::hittable::Mesh;
+use crate::material::Material;
+use crate::texture::Texture;
+use crate::vec3::{Color, Vec3};
+
+#[derive(Clone, Copy, PartialEq)]
+enum Format {
+    Ascii,
+    BinaryLittleEndian,
+}
+
+#[derive(Clone, Copy)]
+enum ScalarType {
+    Int8,
+    UInt8,
+    Int16,
+    UInt16,
+    Int32,
+    UInt32,
+    Float32,
+    Float64,
+}
+
+impl ScalarType {
+    fn parse(name: &str) -> Option<Self> {
+        match name {
+            "char" | "int8" => Some(Self::Int8),
+            "uchar" | "uint8" => Some(Self::UInt8),
+            "short" | "int16" => Some(Self::Int16),
+            "ushort" | "uint16" => Some(Self::UInt16),
+            "int" | "int32" => Some(Self::Int32),
+            "uint" | "uint32" => Some(Self::UInt32),
+            "float" | "float32" => Some(Self::Float32),
+            "double" | "float64" => Some(Self::Float64),
+            _ => None,
+        }
+    }
+
+    fn size(self) -> usize {
+        match self {
+            Self::Int8 | Self::UInt8 => 1,
+            Self::Int16 | Self::UInt16 => 2,
+            Self::Int32 | Self::UInt32 | Self::Float32 => 4,
+            Self::Float64 => 8,
+        }
+    }
+
+    fn is_integer(self) -> bool {
+        !matches!(self, Self::Float32 | Self::Float64)
+    }
+
+    fn max_value(self) -> f64 {
+        match self {
+            Self::Int8 => i8::MAX as f64,
+            Self::UInt8 => u8::MAX as f64,
+            Self::Int16 => i16::MAX as f64,
+            Self::UInt16 => u16::MAX as f64,
+            Self::Int32 => i32::MAX as f64,
+            Self::UInt32 => u32::MAX as f64,
+            Self::Float32 | Self::Float64 => 1.0,
+        }
+    }
+
+    fn read_le(self, bytes: &[u8]) -> f64 {
+        let mut buf = [0u8; 8];
+        buf[..bytes.len()].copy_from_slice(bytes);
+        match self {
+            Self::Int8 => bytes[0] as i8 as f64,
+            Self::UInt8 => bytes[0] as f64,
+            Self::Int16 => i16::from_le_bytes([buf[0], buf[1]]) as f64,
+            Self::UInt16 => u16::from_le_bytes([buf[0], buf[1]]) as f64,
+            Self::Int32 => i32::from_le_bytes([buf[0], buf[1], buf[2], buf[3]]) as f64,
+            Self::UInt32 => u32::from_le_bytes([buf[0], buf[1], buf[2], buf[3]]) as f64,
+            Self::Float32 => f32::from_le_bytes([buf[0], buf[1], buf[2], buf[3]]) as f64,
+            Self::Float64 => f64::from_le_bytes(buf),
+        }
+    }
+}
+
+enum PropertyType {
+    Scalar(ScalarType),
+    List { count: ScalarType, item: ScalarType },
+}
+
+struct Property {
+    name: String,
+    kind: PropertyType,
+}
+
+struct Element {
+    name: String,
+    count: usize,
+    properties: Vec<Property>,
+}
+
+impl Element {
+    fn scalar(&self, names: &[&str]) -> Result<Option<(usize, ScalarType)>, String> {
+        let found = self
+            .properties
+            .iter()
+            .enumerate()
+            .find(|(_, p)| names.contains(&p.name.as_str()));
+
+        match found {
+            Some((
+                i,
+                Property {
+                    kind: PropertyType::Scalar(scalar),
+                    ..
+                },
+            )) => Ok(Some((i, *scalar))),
+            Some((_, property)) => Err(format!(
+                "Unsupported list property \"{}\" on element \"{}\"",
+                property.name, self.name
+            )),
+            None => Ok(None),
+        }
+    }
+}
+
+enum Value {
+    Scalar(f64),
+    List(Vec<f64>),
+}
+
+impl Value {
+    fn scalar(&self) -> f64 {
+        match self {
+            Self::Scalar(value) => *value,
+            Self::List(values) => values.first().copied().unwrap_or(0.0),
+        }
+    }
+}
+
+struct Reader<'a> {
+    format: Format,
+    data: &'a [u8],
+    position: usize,
+    tokens: std::str::SplitAsciiWhitespace<'a>,
+}
+
+impl Reader<'_> {
+    fn read(&mut self, scalar: ScalarType) -> Result<f64, String> {
+        match self.format {
+            Format::Ascii => {
+                let token = self.tokens.next().ok_or("Unexpected end of file")?;
+                token
+                    .parse()
+                    .map_err(|_| format!("Expected a number, got \"{}\"", token))
+            }
+            Format::BinaryLittleEndian => {
+                let size = scalar.size();
+                let bytes = self
+                    .data
+                    .get(self.position..self.position + size)
+                    .ok_or("Unexpected end of file")?;
+                self.position += size;
+                Ok(scalar.read_le(bytes))
+            }
+        }
+    }
+
+    fn read_element(&mut self, element: &Element) -> Result<Vec<Value>, String> {
+        element
+            .properties
+            .iter()
+            .map(|property| match property.kind {
+                PropertyType::Scalar(scalar) => Ok(Value::Scalar(self.read(scalar)?)),
+                PropertyType::List { count, item } => {
+                    let len = self.read(count)? as usize;
+                    let items = (0..len)
+                        .map(|_| self.read(item))
+                        .collect::<Result<_, _>>()?;
+                    Ok(Value::List(items))
+                }
+            })
+            .collect()
+    }
+}
+
+// Loads the vertex and face elements of an ASCII or binary little endian PLY file as a mesh.
+// Quads are split in two triangles. The mesh shows its vertex colors when it has some, and is
+// grey otherwise.
+pub fn load_ply(path: &Path) -> Result<Mesh, String> {
+    let data = std::fs::read(path)
+        .map_err(|err| format!("Could not read \"{}\": {}", path.display(), err))?;
+    parse_ply(&data).map_err(|err| format!("{}: {}", path.display(), err))
+}
+
+fn parse_ply(data: &[u8]) -> Result<Mesh, String> {
+    let (format, elements, body_start) = parse_header(data)?;
+
+    let body = &data[body_start..];
+    let ascii_body = match format {
+        Format::Ascii => {
+            std::str::from_utf8(body).map_err(|_| "ASCII body is not valid text".to_string())?
+        }
+        Format::BinaryLittleEndian => "",
+    };
+    let mut reader = Reader {
+        format,
+        data: body,
+        position: 0,
+        tokens: ascii_body.split_ascii_whitespace(),
+    };
+
+    let mut positions = vec![];
+    let mut normals = vec![];
+    let mut uvs = vec![];
+    let mut colors = vec![];
+    let mut indices = vec![];
+    let vertex_count = elements
+        .iter()
+        .find(|element| element.name == "vertex")
+        .map_or(0, |element| element.count);
+
+    for element in elements.iter() {
+        match element.name.as_str() {
+            "vertex" => {
+                let x = element.scalar(&["x"])?;
+                let y = element.scalar(&["y"])?;
+                let z = element.scalar(&["z"])?;
+                let (x, y, z) = match (x, y, z) {
+                    (Some(x), Some(y), Some(z)) => (x.0, y.0, z.0),
+                    _ => return Err("Vertices need x, y and z properties".to_string()),
+                };
+                let normal = match (
+                    element.scalar(&["nx"])?,
+                    element.scalar(&["ny"])?,
+                    element.scalar(&["nz"])?,
+                ) {
+                    (Some(nx), Some(ny), Some(nz)) => Some((nx.0, ny.0, nz.0)),
+                    _ => None,
+                };
+                let uv = match (
+                    element.scalar(&["u", "s", "texture_u", "texture_s"])?,
+                    element.scalar(&["v", "t", "texture_v", "texture_t"])?,
+                ) {
+                    (Some(u), Some(v)) => Some((u.0, v.0)),
+                    _ => None,
+                };
+                let color = match (
+                    element.scalar(&["red", "r", "diffuse_red"])?,
+                    element.scalar(&["green", "g", "diffuse_green"])?,
+                    element.scalar(&["blue", "b", "diffuse_blue"])?,
+                ) {
+                    (Some(r), Some(g), Some(b)) => Some((r, g, b)),
+                    _ => None,
+                };
+
+                for _ in 0..element.count {
+                    let values = reader.read_element(element)?;
+                    positions.push(Vec3::new(
+                        values[x].scalar(),
+                        values[y].scalar(),
+                        values[z].scalar(),
+                    ));
+                    if let Some((nx, ny, nz)) = normal {
+                        normals.push(Vec3::new(
+                            values[nx].scalar(),
+                            values[ny].scalar(),
+                            values[nz].scalar(),
+                        ));
+                    }
+                    if let Some((u, v)) = uv {
+                        uvs.push((values[u].scalar(), values[v].scalar()));
+                    }
+                    if let Some((r, g, b)) = color {
+                        // Integer colors are normalized to [0, 1]
+                        let channel = |(i, scalar): (usize, ScalarType)| {
+                            values[i].scalar() / scalar.max_value()
+                        };
+                        colors.push(Color::new(channel(r), channel(g), channel(b)));
+                    }
+                }
+            }
+            "face" => {
+                let list = element
+                    .properties
+                    .iter()
+                    .position(|p| p.name == "vertex_indices" || p.name == "vertex_index")
+                    .ok_or_else(|| "Faces need a vertex_indices list property".to_string())?;
+                match element.properties[list].kind {
+                    PropertyType::List { item, .. } if item.is_integer() => {}
+                    _ => return Err("vertex_indices must be a list of integers".to_string()),
+                }
+
+                for face_index in 0..element.count {
+                    let values = reader.read_element(element)?;
+                    let face = match &values[list] {
+                        Value::List(face) => face,
+                        Value::Scalar(_) => unreachable!(),
+                    };
+
+                    let face = face
+                        .iter()
+                        .map(|&i| {
+                            usize::try_from(i as i64)
+                                .ok()
+                                .filter(|&i| i < vertex_count)
+                                .ok_or_else(|| {
+                                    format!(
+                                        "Face {} index {} is out of range ({} vertices)",
+                                        face_index, i, vertex_count
+                                    )
+                                })
+                        })
+                        .collect::<Result<Vec<usize>, String>>()?;
+                    match face.len() {
+                        3 => indices.extend_from_slice(&face),
+                        4 => indices.extend_from_slice(&[
+                            face[0], face[1], face[2], face[0], face[2], face[3],
+                        ]),
+                        len => {
+                            return Err(format!(
+                                "Face {} has {} vertices, only triangles and quads are supported",
+                                face_index, len
+                            ))
+                        }
+                    }
+                }
+            }
+            _ => {
+                for _ in 0..element.count {
+                    reader.read_element(element)?;
+                }
+            }
+        }
+    }
+
+    let albedo = if colors.is_empty() {
+        Texture::solid(Color::new(0.8, 0.8, 0.8))
+    } else {
+        Arc::new(Texture::VertexColor)
+    };
+    let material = Arc::new(Material::Lambertian { albedo });
+    Mesh::new(positions, normals, uvs, colors, indices, material)
+}
+
+fn parse_header(data: &[u8]) -> Result<(Format, Vec<Element>, usize), String> {
+    let mut format = None;
+    let mut elements: Vec<Element> = vec![];
+    let mut position = 0;
+    let mut first_line = true;
+
+    loop {
+        let end = data[position..]
+            .iter()
+            .position(|&b| b == b'\n')
+            .ok_or("Missing end_header")?;
+        let line = std::str::from_utf8(&data[position..position + end])
+            .map_err(|_| "Header is not valid text")?
+            .trim();
+        position += end + 1;
+
+        if first_line {
+            if line != "ply" {
+                return Err("Not a PLY file".to_string());
+            }
+            first_line = false;
+            continue;
+        }
+
+        let tokens: Vec<&str> = line.split_whitespace().collect();
+        match tokens.as_slice() {
+            ["format", "ascii", _] => format = Some(Format::Ascii),
+            ["format", "binary_little_endian", _] => format = Some(Format::BinaryLittleEndian),
+            ["format", other, _] => return Err(format!("Unsupported format \"{}\"", other)),
+            ["element", name, count] => elements.push(Element {
+                name: name.to_string(),
+                count: count
+                    .parse()
+                    .map_err(|_| format!("Bad element count \"{}\"", count))?,
+                properties: vec![],
+            }),
+            ["property", "list", count, item, name] => {
+                let element = elements
+                    .last_mut()
+                    .ok_or("Property declared before any element")?;
+                let count = ScalarType::parse(count)
+                    .ok_or_else(|| format!("Unsupported property type \"{}\"", count))?;
+                let item = ScalarType::parse(item)
+                    .ok_or_else(|| format!("Unsupported property type \"{}\"", item))?;
+                element.properties.push(Property {
+                    name: name.to_string(),
+                    kind: PropertyType::List { count, item },
+                });
+            }
+            ["property", scalar, name] => {
+                let element = elements
+                    .last_mut()
+                    .ok_or("Property declared before any element")?;
+                let scalar = ScalarType::parse(scalar)
+                    .ok_or_else(|| format!("Unsupported property type \"{}\"", scalar))?;
+                element.properties.push(Property {
+                    name: name.to_string(),
+                    kind: PropertyType::Scalar(scalar),
+                });
+            }
+            ["end_header"] => break,
+            ["comment", ..] | ["obj_info", ..] | [] => {}
+            _ => return Err(format!("Unexpected header line \"{}\"", line)),
+        }
+    }
+
+    let format = format.ok_or("Missing format line")?;
+    Ok((format, elements, position))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::parse_ply;
+
+    fn ascii_ply(faces: &str) -> String {
+        format!(
+            "ply
+format ascii 1.0
+element vertex 4
+property float x
+property float y
+property float z
+element face {}
+property list uchar int vertex_indices
+end_header
+0 0 0
+1 0 0
+1 1 0
+0 1 0
+{}",
+            faces.lines().count(),
+            faces
+        )
+    }
+
+    #[test]
+    fn loads_triangles_and_quads() {
+        let mesh = parse_ply(ascii_ply("3 0 1 2\n4 0 1 2 3\n").as_bytes()).unwrap();
+        assert_eq!(mesh.triangle_count(), 3);
+    }
+
+    #[test]
+    fn rejects_negative_indices() {
+        let err = parse_ply(ascii_ply("3 0 -1 2\n").as_bytes()).err().unwrap();
+        assert_eq!(err, "Face 0 index -1 is out of range (4 vertices)");
+    }
+
+    #[test]
+    fn rejects_out_of_range_indices() {
+        let err = parse_ply(ascii_ply("3 0 1 2\n3 0 2 4\n").as_bytes())
+            .err()
+            .unwrap();
+        assert_eq!(err, "Face 1 index 4 is out of range (4 vertices)");
+    }
+}
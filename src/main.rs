@@ -1,257 +1,263 @@
+mod accelerator;
+mod adaptive;
+mod aov;
 mod axis_aligned_bb;
-// mod bvh_node;
+mod bvh_node;
+mod filter;
+mod flat_bvh;
 mod hittable;
+mod integrator;
+mod light;
+mod loaders;
 mod material;
 mod math;
+mod onb;
+mod output;
 mod program_args;
 mod ray;
 mod render_options;
 mod rgbcolor;
+mod rng;
+mod sampler;
 mod scene;
 mod surface;
+mod texture;
+mod tiles;
+mod tonemap;
+mod transform;
+mod traversal;
 mod vec3;
 
-use indicatif::{MultiProgress, ProgressBar, ProgressStyle};
-use rand::prelude::*;
+use indicatif::{ProgressBar, ProgressStyle};
+use std::path::{Path, PathBuf};
 use std::sync::{mpsc::channel, Arc};
+use std::time::Instant;
 
-use hittable::{Hittable, HittableList};
-use material::Material;
-use program_args::ProgramArgs;
-use ray::Ray;
+use adaptive::PixelEstimator;
+use aov::{aov_files, AovValues};
+use integrator::Integrator;
+use output::OutputSettings;
+use program_args::{parse_args, Command};
 use render_options::RenderOptions;
-use rgbcolor::RGBColor;
+use sampler::Sampler;
 use scene::{Camera, Config, Scene};
 use surface::Surface;
-use vec3::{Color, Vec3};
-
-fn random_scene() -> HittableList {
-    let mut world = HittableList::new();
-    let mut rng = thread_rng();
-
-    let ground_material = Material::Lambertian {
-        albedo: Color::new(0.5, 0.5, 0.5),
-    };
+use tiles::{Tile, TileQueue};
+use tonemap::ToneMapping;
+use vec3::{luminance, Color};
+
+// Sample `index` of pixel (x, y), returned with its position on the image
+#[allow(clippy::too_many_arguments)]
+fn render_sample(
+    x: usize,
+    y: usize,
+    index: u32,
+    options: &RenderOptions,
+    cam: &Camera,
+    integrator: &dyn Integrator,
+    sampler: &mut dyn Sampler,
+    aovs: Option<&mut AovValues>,
+) -> (Color, (f64, f64)) {
+    // Samples only depend on the pixel and sample index, not on the tiles or threads
+    sampler.start_sample((x, y), index);
+    let (du, dv) = sampler.get_2d();
+    let u = (x as f64 + du) / (options.img_width - 1) as f64;
+    let v = (y as f64 + dv) / (options.img_height - 1) as f64;
+    let u_lens = sampler.get_2d();
+    let u_time = sampler.get_1d();
+    let ray = cam.get_ray(u, v, u_lens, u_time);
+    let color = integrator.li(ray, sampler, aovs);
+    (color, (x as f64 + du, y as f64 + dv))
+}
 
-    let ground = Hittable::Sphere {
-        center: Vec3::new(0.0, -1000.0, 0.0),
-        radius: 1000.0,
-        material: ground_material,
+fn render_tile(
+    tile: Tile,
+    options: &RenderOptions,
+    cam: &Camera,
+    integrator: &dyn Integrator,
+    progress_bar: &ProgressBar,
+) -> Surface {
+    // Samples near the tile borders are splatted in the neighboring tiles too, the tile surface
+    // covers them so the tiles merge seamlessly
+    let margin = options.filter.margin();
+    let x0 = tile.x.saturating_sub(margin);
+    let y0 = tile.y.saturating_sub(margin);
+    let x1 = std::cmp::min(tile.x + tile.width + margin, options.img_width);
+    let y1 = std::cmp::min(tile.y + tile.height + margin, options.img_height);
+    let mut surface = Surface::new(x0, y0, x1 - x0, y1 - y0, &options.aovs);
+    let mut layers = vec![Color::zero(); options.aovs.len()];
+    let mut sampler = options
+        .sampler
+        .create(options.sample_per_pixel, options.seed);
+
+    let max_samples = options.sample_per_pixel;
+    let min_samples = match &options.adaptive {
+        Some(adaptive) => std::cmp::min(adaptive.min_samples, max_samples),
+        None => max_samples,
     };
-    world.add(Arc::new(ground));
-
-    for a in -11..11 {
-        for b in -11..11 {
-            let choose_mat = rng.gen::<f64>();
-            let center = Vec3::new(
-                a as f64 + 0.9 * rng.gen::<f64>(),
-                0.2,
-                b as f64 + 0.9 * rng.gen::<f64>(),
-            );
-
-            if (center - Vec3::new(4.0, 0.2, 0.0)).length() > 0.9 {
-                if choose_mat < 0.8 {
-                    let albedo = Vec3::random_color() * Vec3::random_color();
-                    let center1 = center + Vec3::new(0.0, rng.gen_range(0.0..=0.5), 0.0);
-
-                    let sphere = Hittable::MovingSphere {
-                        center0: center,
-                        center1,
-                        time0: 0.0,
-                        time1: 1.0,
-                        radius: 0.2,
-                        material: Material::Lambertian { albedo },
-                    };
 
-                    world.add(Arc::new(sphere));
-                } else if choose_mat < 0.95 {
-                    let albedo = Vec3::random_color_range(0.5, 1.0);
-                    let fuzz = rng.gen_range(0.0..=0.5);
-
-                    let sphere = Hittable::Sphere {
-                        center,
-                        radius: 0.2,
-                        material: Material::Metal { albedo, fuzz },
-                    };
+    for j in 0..tile.height {
+        for i in 0..tile.width {
+            if (i + j * tile.width).is_multiple_of(options.progress_tick_rate) {
+                progress_bar.tick();
+            }
 
-                    world.add(Arc::new(sphere));
-                } else {
-                    let sphere = Hittable::Sphere {
-                        center,
-                        radius: 0.2,
-                        material: Material::Dielectric { ref_idx: 1.5 },
+            let x = i + tile.x;
+            let y = j + tile.y;
+
+            // Adaptive sampling takes `min_samples` and then batches of `step` samples until
+            // the pixel is converged
+            let mut estimator = PixelEstimator::default();
+            let mut sample_count = 0;
+            let mut batch_end = min_samples;
+            while sample_count < max_samples {
+                while sample_count < batch_end {
+                    // AOVs are only computed when they are saved
+                    let mut aov_values = AovValues::default();
+                    let aovs = if layers.is_empty() {
+                        None
+                    } else {
+                        Some(&mut aov_values)
                     };
+                    let (color, position) = render_sample(
+                        x,
+                        y,
+                        sample_count,
+                        options,
+                        cam,
+                        integrator,
+                        sampler.as_mut(),
+                        aovs,
+                    );
+                    for (layer, &aov) in layers.iter_mut().zip(options.aovs.iter()) {
+                        *layer = aov_values.get(aov);
+                    }
+                    surface.add_sample(x, y, position, color, &layers, &options.filter);
+                    estimator.add(luminance(color));
+                    sample_count += 1;
+                }
 
-                    world.add(Arc::new(sphere));
+                match &options.adaptive {
+                    Some(adaptive) if estimator.relative_error() > adaptive.threshold => {
+                        batch_end = std::cmp::min(
+                            sample_count + std::cmp::max(adaptive.step, 1),
+                            max_samples,
+                        );
+                    }
+                    _ => break,
                 }
             }
         }
     }
 
-    let glass_sphere = Hittable::Sphere {
-        center: Vec3::new(0.0, 1.0, 0.0),
-        radius: 1.0,
-        material: Material::Dielectric { ref_idx: 1.5 },
-    };
-    world.add(Arc::new(glass_sphere));
-
-    let mat_sphere = Hittable::Sphere {
-        center: Vec3::new(-4.0, 1.0, 0.0),
-        radius: 1.0,
-        material: Material::Lambertian {
-            albedo: Color::new(0.4, 0.2, 0.1),
-        },
-    };
-    world.add(Arc::new(mat_sphere));
-
-    let metal_sphere = Hittable::Sphere {
-        center: Vec3::new(4.0, 1.0, 0.0),
-        radius: 1.0,
-        material: Material::Metal {
-            albedo: Color::new(0.7, 0.6, 0.5),
-            fuzz: 0.0,
-        },
-    };
-    world.add(Arc::new(metal_sphere));
-
-    world
-}
-
-fn scale_color(color: Color, spp: u32) -> RGBColor {
-    let mut r = color.x;
-    let mut g = color.y;
-    let mut b = color.z;
-
-    let scale = 1.0 / spp as f64;
-    r = (scale * r).sqrt();
-    g = (scale * g).sqrt();
-    b = (scale * b).sqrt();
-
-    RGBColor::new(
-        (256.0 * math::clamp(r, 0.0, 0.999)) as u8,
-        (256.0 * math::clamp(g, 0.0, 0.999)) as u8,
-        (256.0 * math::clamp(b, 0.0, 0.999)) as u8,
-    )
+    surface
 }
 
-fn ray_color(
-    ray: Ray,
-    world_ptr: Arc<HittableList>,
-    options: &RenderOptions,
-    y: usize,
-    depth: u32,
-) -> Color {
-    if depth == 0 {
-        return Color::new(0.0, 0.0, 0.0);
-    }
-
-    if let Some(intersection) = world_ptr.hit(ray, 0.001, std::f64::INFINITY) {
-        if let Some((attenuation, scattered)) = intersection.material.scatter(ray, &intersection) {
-            attenuation * ray_color(scattered, world_ptr, &options, y, depth - 1)
-        } else {
-            Color::new(0.0, 0.0, 0.0)
-        }
+fn get_job_count(arg: usize) -> usize {
+    let available_threads = num_cpus::get();
+    if arg == 0 || arg > available_threads {
+        std::cmp::max(available_threads - 1, 1)
     } else {
-        options
-            .background
-            .get_color(y as f64 / options.img_height as f64)
+        arg
     }
 }
 
-fn render_surface(
-    x_offset: usize,
-    y_offset: usize,
-    height: usize,
-    options: RenderOptions,
-    cam: Camera,
-    world_ptr: Arc<HittableList>,
-    progress_bar: ProgressBar,
-) -> Surface {
-    let mut rng = thread_rng();
-    let mut surface = Surface::new(x_offset, y_offset, options.img_width, height);
-
-    let mut msg_str_len = 0;
-    for j in 0..height {
-        let msg = format!("Rendering scanline #{}", j + 1);
-        msg_str_len = msg.len();
-        progress_bar.set_message(msg.as_str());
-        progress_bar.inc(1);
+fn render(scene: &Scene, options: &RenderOptions, thread_count: usize) -> Surface {
+    // The image is split in tiles taken from a shared queue by the threads as they finish their
+    // previous tile. Tiles overlap by the filter margin, so they are merged in queue order to sum
+    // the samples in the same order whatever thread finishes first.
+    let queue = Arc::new(TileQueue::new(
+        options.img_width,
+        options.img_height,
+        options.tile_size,
+        options.tile_order,
+    ));
+
+    let progress_bar = ProgressBar::new(queue.len() as u64);
+    progress_bar.set_style(
+        ProgressStyle::default_bar()
+            .template("[{elapsed_precise}] {bar:40.yellow/cyan} {pos:>5}/{len:5} tiles (eta {eta})")
+            .progress_chars("=>-"),
+    );
 
-        for i in 0..options.img_width {
-            let mut color = Color::new(0.0, 0.0, 0.0);
+    let integrator = options.integrator.create(scene, options);
 
-            if i % options.progress_tick_rate == 0 {
-                progress_bar.tick();
-            }
+    let (tx, rx) = channel();
+    for _ in 0..thread_count {
+        let camera = scene.get_camera();
+        let integrator = integrator.clone();
+        let local_options = options.clone();
+        let queue = queue.clone();
+        let progress_bar = progress_bar.clone();
+        let child_tx = tx.clone();
 
-            for _s in 0..options.sample_per_pixel {
-                let u = ((i + x_offset) as f64 + rng.gen::<f64>()) / (options.img_width - 1) as f64;
-                let v =
-                    ((j + y_offset) as f64 + rng.gen::<f64>()) / (options.img_height - 1) as f64;
-                let ray = cam.get_ray(u, v);
-                color += ray_color(
-                    ray,
-                    world_ptr.clone(),
-                    &options,
-                    j + y_offset,
-                    options.max_depth,
+        std::thread::spawn(move || {
+            while let Some(tile) = queue.next() {
+                let surface = render_tile(
+                    tile,
+                    &local_options,
+                    &camera,
+                    integrator.as_ref(),
+                    &progress_bar,
                 );
+                if let Err(err) = child_tx.send((tile.index, surface)) {
+                    eprintln!("{}", err);
+                    return;
+                }
             }
-
-            surface.set_color(i, j, scale_color(color, options.sample_per_pixel));
-        }
+        });
     }
 
-    // 5 is length of str "Done "
-    let msg = format!("Done {:len$}", " ", len = msg_str_len - 5);
-    progress_bar.finish_with_message(msg.as_str());
-
-    surface
-}
-
-fn parse_file(file_path: &str) -> Result<Config, std::io::Error> {
-    let file = std::fs::File::open(file_path)?;
-
-    let data = serde_json::from_reader(file)?;
-    Ok(data)
-}
+    drop(tx);
 
-fn parse_args() -> Result<ProgramArgs, String> {
-    let args: Vec<String> = std::env::args().collect();
-    if !(args.len() == 2 || args.len() == 3) {
-        Err(format!(
-            "Usage: {} config_file.json job_count (note: job_count is optional)",
-            &args[0]
-        ))
-    } else if args.len() == 2 {
-        let file_path = args[1].clone();
-        Ok(ProgramArgs {
-            file_path,
-            job_count: 0,
-        })
-    } else {
-        let file_path = args[1].clone();
-        match args[2].parse() {
-            Ok(job_count) => Ok(ProgramArgs {
-                file_path,
-                job_count,
-            }),
-            Err(_) => Err("Job count must be an unsigned number".to_string()),
+    let mut img = Surface::new(0, 0, options.img_width, options.img_height, &options.aovs);
+    let mut pending: Vec<Option<Surface>> = (0..queue.len()).map(|_| None).collect();
+    let mut next = 0;
+    for (index, tile) in rx.iter() {
+        pending[index] = Some(tile);
+        while let Some(tile) = pending.get_mut(next).and_then(Option::take) {
+            img.merge(&tile);
+            next += 1;
         }
+        progress_bar.inc(1);
     }
+    progress_bar.finish();
+
+    img
 }
 
-fn get_job_count(arg: usize) -> usize {
-    let available_threads = num_cpus::get();
-    if arg == 0 || arg > available_threads {
-        available_threads - 1
-    } else {
-        arg
+fn print_info(cfg: &Config, scene: &Scene, output_settings: &OutputSettings) {
+    println!(
+        "Image: {}x{}, {} samples per pixel, max depth {}",
+        scene.img_width, scene.img_height, scene.sample_per_pixel, scene.max_depth
+    );
+    println!("Scene: {}", scene.get_stats());
+    if let Some(bounds) = scene.get_stats().bounds {
+        let (min, max) = (bounds.min(), bounds.max());
+        println!(
+            "Bounds: ({:.3}, {:.3}, {:.3}) to ({:.3}, {:.3}, {:.3})",
+            min.x, min.y, min.z, max.x, max.y, max.z
+        );
+    }
+    println!(
+        "Assets: {} textures, {} materials",
+        cfg.textures.len(),
+        cfg.materials.len()
+    );
+    if let Some(stats) = scene.get_objects().stats() {
+        println!("BVH: {}", stats);
     }
+    println!(
+        "Output: {} ({:?}, {} bits)",
+        output_settings.path.display(),
+        output_settings.format,
+        output_settings.bit_depth
+    );
 }
 
 fn main() {
-    let args = match parse_args() {
+    let args: Vec<String> = std::env::args().collect();
+    let args = match parse_args(&args) {
         Ok(args) => args,
         Err(err) => {
             eprintln!("{}", err);
@@ -259,7 +265,7 @@ fn main() {
         }
     };
 
-    let cfg: Config = match parse_file(args.file_path.as_str()) {
+    let cfg = match Config::load(&args.file_path, &args.overrides) {
         Ok(config) => config,
         Err(err) => {
             eprintln!("{}", err);
@@ -267,92 +273,113 @@ fn main() {
         }
     };
 
-    let scene = Scene::new(&cfg, random_scene());
-
-    let thread_count = get_job_count(args.job_count);
-    println!("Using {} threads", thread_count);
-
-    // Each thread renders an image wide strip of the final image like shown below
-    // --------------------------------------
-    // |                                    |
-    // --------------------------------------
-    // |                                    |
-    // --------------------------------------
-    // |                                    |
-    // --------------------------------------
-    let section_height = cfg.img_height / thread_count;
-    let mut extra_pixels = cfg.img_height % thread_count;
-
-    let (tx, rx) = channel();
+    let output_path = args
+        .output
+        .as_ref()
+        .map(PathBuf::from)
+        .or_else(|| cfg.output.clone());
+    let output_settings = match OutputSettings::new(
+        output_path.as_deref(),
+        cfg.output_format,
+        cfg.bit_depth,
+        cfg.jpeg_quality,
+        ToneMapping::new(cfg.tone_map, cfg.exposure),
+    ) {
+        Ok(settings) => settings,
+        Err(err) => {
+            eprintln!("{}", err);
+            std::process::exit(1);
+        }
+    };
 
-    // Multi progress bars setup
-    let multi_progress = MultiProgress::new();
-    multi_progress.set_move_cursor(true);
-    let progress_style = ProgressStyle::default_bar()
-        .template("[{elapsed_precise}] {prefix:>10}: {bar:40.yellow/cyan} {pos:>5}/{len:5} {msg}")
-        .progress_chars("=>-");
-
-    let render_options = RenderOptions::new(
-        cfg.progress_tick_rate,
-        cfg.img_width,
-        cfg.img_height,
-        cfg.sample_per_pixel,
-        cfg.max_depth,
-        cfg.background,
-    );
+    let heatmap_settings = match cfg.adaptive.as_ref().and_then(|a| a.heatmap.as_ref()) {
+        Some(path) => match OutputSettings::new(
+            Some(Path::new(path)),
+            None,
+            None,
+            cfg.jpeg_quality,
+            ToneMapping::default(),
+        ) {
+            Ok(settings) => Some(settings),
+            Err(err) => {
+                eprintln!("Heatmap: {}", err);
+                std::process::exit(1);
+            }
+        },
+        None => None,
+    };
 
-    let mut height_offset = 0;
-    for i in 0..thread_count {
-        let camera = scene.get_camera();
-        let world_ptr = scene.get_objects();
-        let local_options = render_options.clone();
+    // Checked before rendering so a long render isn't thrown away
+    if args.command == Command::Render && !args.force {
+        let aov_settings = aov_files(&output_settings, &cfg.aovs, cfg.aov_output);
+        for settings in std::iter::once(&output_settings)
+            .chain(heatmap_settings.iter())
+            .chain(aov_settings.iter().map(|(_, settings)| settings))
+        {
+            if settings.path.exists() {
+                eprintln!(
+                    "{} already exists, use --force to overwrite it",
+                    settings.path.display()
+                );
+                std::process::exit(1);
+            }
+        }
+    }
 
-        let child_tx = tx.clone();
+    let scene = match Scene::new(&cfg) {
+        Ok(scene) => scene,
+        Err(err) => {
+            eprintln!("{}", err);
+            std::process::exit(1);
+        }
+    };
 
-        // Spread extra pixels evenly across threads
-        let surface_height = if extra_pixels > 0 {
-            extra_pixels -= 1;
-            section_height + 1
-        } else {
-            section_height
-        };
+    match args.command {
+        Command::Validate => {
+            println!("{} is valid ({})", args.file_path, scene.get_stats());
+            return;
+        }
+        Command::Info => {
+            print_info(&cfg, &scene, &output_settings);
+            return;
+        }
+        Command::Render | Command::Bench => {}
+    }
 
-        // Individual progress bar setup
-        let progress_bar = multi_progress.add(ProgressBar::new(surface_height as u64));
-        progress_bar.set_style(progress_style.clone());
-        let msg = format!("Thread {}", i);
-        progress_bar.set_prefix(msg.as_str());
+    if let Some(stats) = scene.get_objects().stats() {
+        println!("BVH: {}", stats);
+    }
 
-        std::thread::spawn(move || {
-            if let Err(err) = child_tx.send(render_surface(
-                0,
-                height_offset,
-                surface_height,
-                local_options,
-                camera,
-                world_ptr,
-                progress_bar,
-            )) {
-                eprintln!("{}", err);
-            }
-        });
+    let thread_count = get_job_count(args.job_count);
+    println!("Using {} threads", thread_count);
 
-        height_offset += surface_height;
+    let render_options = RenderOptions::new(&cfg, &scene);
+
+    let start = Instant::now();
+    let img = render(&scene, &render_options, thread_count);
+    let elapsed = start.elapsed();
+
+    if args.command == Command::Bench {
+        let samples = img.total_samples() as f64;
+        let pixels = (scene.img_width * scene.img_height) as f64;
+        println!("Scene: {}", scene.get_stats());
+        println!(
+            "Rendered in {:.2?} ({:.1} samples per pixel, {:.0} samples/s, {:.0} per thread)",
+            elapsed,
+            samples / pixels,
+            samples / elapsed.as_secs_f64(),
+            samples / elapsed.as_secs_f64() / thread_count as f64
+        );
+        return;
     }
 
-    if let Err(err) = multi_progress.join() {
+    if let Err(err) = img.save(&output_settings, &cfg.aovs, cfg.aov_output) {
         eprintln!("{}", err);
-    };
-
-    drop(tx);
-
-    // Merge every portion of the image in output image
-    let mut img = Surface::new(0, 0, render_options.img_width, render_options.img_height);
-    for result in rx.iter() {
-        img.merge(&result);
     }
 
-    if let Err(err) = img.save("output.png") {
-        eprintln!("{}", err);
+    if let Some(settings) = heatmap_settings {
+        if let Err(err) = img.save_sample_counts(&settings, scene.sample_per_pixel) {
+            eprintln!("{}", err);
+        }
     }
 }
@@ -1,83 +1,167 @@
-use rand::Rng;
+use std::f64::consts::PI;
+use std::sync::Arc;
 
 use crate::hittable::Intersection;
 use crate::math::schlick;
-use crate::ray::Ray;
+use crate::sampler::{sample_cosine_hemisphere, sample_in_unit_sphere};
+use crate::texture::Texture;
 use crate::vec3::{reflect, refract, Color, Vec3};
 
+#[derive(Clone)]
 pub enum Material {
-    Lambertian { albedo: Color },
-    Metal { albedo: Color, fuzz: f64 },
+    Lambertian { albedo: Arc<Texture> },
+    Metal { albedo: Arc<Texture>, fuzz: f64 },
     Dielectric { ref_idx: f64 },
+    DiffuseLight { color: Color, intensity: f64 },
 }
 
+// Direction picked by `Material::sample`. `f` is the BSDF value and `pdf` the density of
+// picking `wi`, the light carried along `wi` is scaled by `f * cos / pdf`.
+pub struct BsdfSample {
+    pub wi: Vec3,
+    pub f: Color,
+    pub pdf: f64,
+    // Specular directions can't be found by `eval` and `pdf`, the density is only relative to
+    // the other directions the material could pick
+    pub specular: bool,
+}
+
+// Directions are in the local shading frame of the intersection (the normal is the z axis),
+// they point away from the surface: `wo` toward the viewer and `wi` toward the light.
 impl Material {
-    pub fn scatter(&self, ray: Ray, intersection: &Intersection) -> Option<(Color, Ray)> {
+    // `u` and `uc` are the sample values used to pick the direction
+    pub fn sample(
+        &self,
+        intersection: &Intersection,
+        wo: Vec3,
+        u: (f64, f64),
+        uc: f64,
+    ) -> Option<BsdfSample> {
+        match self {
+            Self::Lambertian { albedo } => lambertian_sample(albedo.value(intersection), wo, u),
+            Self::Metal { albedo, fuzz } => {
+                metal_sample(albedo.value(intersection), *fuzz, wo, u, uc)
+            }
+            Self::Dielectric { ref_idx } => {
+                dielectric_sample(*ref_idx, intersection.front_face, wo, uc)
+            }
+            Self::DiffuseLight { .. } => None,
+        }
+    }
+
+    // BSDF value for the pair of directions, without the cosine term
+    pub fn eval(&self, intersection: &Intersection, wo: Vec3, wi: Vec3) -> Color {
+        match self {
+            Self::Lambertian { albedo } if same_hemisphere(wo, wi) => {
+                albedo.value(intersection) / PI
+            }
+            _ => Color::zero(),
+        }
+    }
+
+    // Density of `sample` picking `wi`, in solid angle
+    pub fn pdf(&self, wo: Vec3, wi: Vec3) -> f64 {
+        match self {
+            Self::Lambertian { .. } if same_hemisphere(wo, wi) => wi.z.abs() / PI,
+            _ => 0.0,
+        }
+    }
+
+    // Materials only scattering in specular directions, `eval` and `pdf` are 0 for them
+    pub fn is_specular(&self) -> bool {
+        matches!(self, Self::Metal { .. } | Self::Dielectric { .. })
+    }
+
+    // Reflectance written to the albedo AOV
+    pub fn albedo(&self, intersection: &Intersection) -> Color {
+        match self {
+            Self::Lambertian { albedo } | Self::Metal { albedo, .. } => albedo.value(intersection),
+            Self::Dielectric { .. } => Color::new(1.0, 1.0, 1.0),
+            Self::DiffuseLight { color, .. } => *color,
+        }
+    }
+
+    // Lights only emit from their front face
+    pub fn emitted(&self, intersection: &Intersection) -> Color {
+        match self.emission() {
+            Some(emission) if intersection.front_face => emission,
+            _ => Color::zero(),
+        }
+    }
+
+    pub fn emission(&self) -> Option<Color> {
         match *self {
-            Self::Lambertian { albedo } => lambertian_scatter(albedo, ray, intersection),
-            Self::Metal { albedo, fuzz } => metal_scatter(albedo, fuzz, ray, intersection),
-            Self::Dielectric { ref_idx } => dielectric_scatter(ref_idx, ray, intersection),
+            Self::DiffuseLight { color, intensity } => Some(intensity * color),
+            _ => None,
         }
     }
 }
 
-fn lambertian_scatter(
-    albedo: Color,
-    ray: Ray,
-    intersection: &Intersection,
-) -> Option<(Color, Ray)> {
-    let scatter_dir = (intersection.normal + Vec3::random_unit_vector()).normalize();
-    let scattered = Ray::new(intersection.point, scatter_dir, ray.time);
-    let attenuation = albedo;
-    Some((attenuation, scattered))
+fn same_hemisphere(wo: Vec3, wi: Vec3) -> bool {
+    wo.z * wi.z > 0.0
 }
 
-fn metal_scatter(
-    albedo: Color,
-    fuzz: f64,
-    ray: Ray,
-    intersection: &Intersection,
-) -> Option<(Color, Ray)> {
-    let reflected = reflect(ray.dir, intersection.normal);
-    let scattered = Ray::new(
-        intersection.point,
-        reflected + fuzz * Vec3::random_vector_in_unit_sphere(),
-        ray.time,
-    );
-    let attenuation = albedo;
-
-    if scattered.dir.dot(intersection.normal) > 0.0 {
-        Some((attenuation, scattered))
-    } else {
-        None
+// Cosine weighted hemisphere on the side of `wo`
+fn lambertian_sample(albedo: Color, wo: Vec3, u: (f64, f64)) -> Option<BsdfSample> {
+    let mut wi = sample_cosine_hemisphere(u);
+    if wo.z < 0.0 {
+        wi.z = -wi.z;
     }
+    if wi.z == 0.0 {
+        return None;
+    }
+
+    Some(BsdfSample {
+        wi,
+        f: albedo / PI,
+        pdf: wi.z.abs() / PI,
+        specular: false,
+    })
 }
 
-fn dielectric_scatter(ref_idx: f64, ray: Ray, intersection: &Intersection) -> Option<(Color, Ray)> {
-    let attenuation = Color::new(1.0, 1.0, 1.0);
-    let etai_over_etat = if intersection.front_face {
-        1.0 / ref_idx
-    } else {
-        ref_idx
-    };
+// Mirror reflection perturbed by a random offset in a sphere of radius `fuzz`
+fn metal_sample(albedo: Color, fuzz: f64, wo: Vec3, u: (f64, f64), uc: f64) -> Option<BsdfSample> {
+    let reflected = Vec3::new(-wo.x, -wo.y, wo.z);
+    let wi = (reflected + fuzz * sample_in_unit_sphere(u, uc)).normalize();
+    if !same_hemisphere(wo, wi) {
+        return None;
+    }
+
+    Some(BsdfSample {
+        wi,
+        f: albedo / wi.z.abs(),
+        pdf: 1.0,
+        specular: true,
+    })
+}
+
+// Reflects with the Fresnel reflectance as probability and refracts otherwise
+fn dielectric_sample(ref_idx: f64, front_face: bool, wo: Vec3, uc: f64) -> Option<BsdfSample> {
+    let etai_over_etat = if front_face { 1.0 / ref_idx } else { ref_idx };
+    let normal = Vec3::new(0.0, 0.0, 1.0_f64.copysign(wo.z));
 
-    let cos_t = -ray.dir.dot(intersection.normal).min(1.0);
+    let cos_t = wo.dot(normal).min(1.0);
     let sin_t = (1.0 - cos_t.powi(2)).sqrt();
 
-    if etai_over_etat * sin_t > 1.0 {
-        let reflected = reflect(ray.dir, intersection.normal);
-        let scattered = Ray::new(intersection.point, reflected, ray.time);
-        return Some((attenuation, scattered));
-    }
+    let (wi, pdf) = if etai_over_etat * sin_t > 1.0 {
+        (reflect(-wo, normal), 1.0)
+    } else {
+        let reflect_prob = schlick(cos_t, etai_over_etat);
+        if uc < reflect_prob {
+            (reflect(-wo, normal), reflect_prob)
+        } else {
+            (refract(-wo, normal, etai_over_etat), 1.0 - reflect_prob)
+        }
+    };
 
-    let reflect_prop = schlick(cos_t, etai_over_etat);
-    if rand::thread_rng().gen::<f64>() < reflect_prop {
-        let reflected = reflect(ray.dir, intersection.normal);
-        let scattered = Ray::new(intersection.point, reflected, ray.time);
-        return Some((attenuation, scattered));
+    if wi.z == 0.0 {
+        return None;
     }
 
-    let refracted = refract(ray.dir, intersection.normal, etai_over_etat);
-    let scattered = Ray::new(intersection.point, refracted, ray.time);
-    Some((attenuation, scattered))
+    Some(BsdfSample {
+        wi,
+        f: Color::new(pdf, pdf, pdf) / wi.z.abs(),
+        pdf,
+        specular: true,
+    })
 }
@@ -24,3 +24,22 @@ pub fn schlick(cos: f64, ref_idx: f64) -> f64 {
     r0 *= r0;
     r0 + (1.0 - r0) * (1.0 - cos).powf(5.0)
 }
+
+#[inline]
+pub fn srgb_to_linear(value: f64) -> f64 {
+    if value <= 0.04045 {
+        value / 12.92
+    } else {
+        ((value + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+// sRGB OETF, the exact inverse of `srgb_to_linear`
+#[inline]
+pub fn linear_to_srgb(value: f64) -> f64 {
+    if value <= 0.0031308 {
+        value * 12.92
+    } else {
+        1.055 * value.powf(1.0 / 2.4) - 0.055
+    }
+}
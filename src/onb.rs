@@ -0,0 +1,26 @@
+use crate::vec3::{coordinate_system, Vec3};
+
+// Orthonormal basis around a normal, the normal is the z axis of the local frame so the cosine
+// of a local direction with the normal is its z coordinate
+#[derive(Debug, Copy, Clone)]
+pub struct Onb {
+    pub u: Vec3,
+    pub v: Vec3,
+    pub w: Vec3,
+}
+
+impl Onb {
+    // `normal` must be normalized
+    pub fn from_normal(normal: Vec3) -> Self {
+        let (u, v) = coordinate_system(normal);
+        Self { u, v, w: normal }
+    }
+
+    pub fn to_local(self, dir: Vec3) -> Vec3 {
+        Vec3::new(dir.dot(self.u), dir.dot(self.v), dir.dot(self.w))
+    }
+
+    pub fn to_world(self, dir: Vec3) -> Vec3 {
+        dir.x * self.u + dir.y * self.v + dir.z * self.w
+    }
+}
@@ -0,0 +1,196 @@
+pub mod exr;
+pub mod hdr;
+pub mod ldr;
+pub mod pfm;
+
+use std::path::{Path, PathBuf};
+
+use serde::Deserialize;
+
+use crate::tonemap::ToneMapping;
+use crate::vec3::Color;
+
+pub use self::exr::{write_exr, ExrPrecision};
+pub use self::hdr::write_hdr;
+pub use self::ldr::write_ldr;
+pub use self::pfm::write_pfm;
+
+const DEFAULT_OUTPUT: &str = "output";
+
+#[derive(Deserialize, Clone, Copy, Debug, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum OutputFormat {
+    #[default]
+    Png,
+    Jpeg,
+    Tiff,
+    Bmp,
+    Pfm,
+    Hdr,
+    Exr,
+}
+
+impl OutputFormat {
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let extension = path.extension()?.to_str()?.to_ascii_lowercase();
+        match extension.as_str() {
+            "png" => Some(Self::Png),
+            "jpg" | "jpeg" => Some(Self::Jpeg),
+            "tif" | "tiff" => Some(Self::Tiff),
+            "bmp" => Some(Self::Bmp),
+            "pfm" => Some(Self::Pfm),
+            "hdr" => Some(Self::Hdr),
+            "exr" => Some(Self::Exr),
+            _ => None,
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Self::Png => "png",
+            Self::Jpeg => "jpg",
+            Self::Tiff => "tiff",
+            Self::Bmp => "bmp",
+            Self::Pfm => "pfm",
+            Self::Hdr => "hdr",
+            Self::Exr => "exr",
+        }
+    }
+
+    // Supported bits per channel, the first one is the default
+    fn bit_depths(&self) -> &'static [u32] {
+        match self {
+            Self::Png | Self::Tiff => &[8, 16],
+            Self::Jpeg | Self::Bmp => &[8],
+            Self::Pfm | Self::Hdr => &[32],
+            Self::Exr => &[16, 32],
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct OutputSettings {
+    pub path: PathBuf,
+    pub format: OutputFormat,
+    pub bit_depth: u32,
+    pub jpeg_quality: u8,
+    pub tone_mapping: ToneMapping,
+}
+
+impl OutputSettings {
+    // The format comes from the path extension, or `format` when no path is given
+    pub fn new(
+        path: Option<&Path>,
+        format: Option<OutputFormat>,
+        bit_depth: Option<u32>,
+        jpeg_quality: u8,
+        tone_mapping: ToneMapping,
+    ) -> Result<Self, String> {
+        let (path, format) = match path {
+            Some(path) => {
+                let path_format = OutputFormat::from_path(path)
+                    .ok_or_else(|| format!("Unknown output format for \"{}\"", path.display()))?;
+                if let Some(format) = format {
+                    if format != path_format {
+                        return Err(format!(
+                            "Output format {:?} doesn't match \"{}\"",
+                            format,
+                            path.display()
+                        ));
+                    }
+                }
+                (path.to_path_buf(), path_format)
+            }
+            None => {
+                let format = format.unwrap_or_default();
+                let path = PathBuf::from(format!("{}.{}", DEFAULT_OUTPUT, format.extension()));
+                (path, format)
+            }
+        };
+
+        let bit_depths = format.bit_depths();
+        let bit_depth = bit_depth.unwrap_or(bit_depths[0]);
+        if !bit_depths.contains(&bit_depth) {
+            return Err(format!(
+                "{:?} output doesn't support a bit depth of {} (supported: {:?})",
+                format, bit_depth, bit_depths
+            ));
+        }
+
+        if !(1..=100).contains(&jpeg_quality) {
+            return Err("JPEG quality must be between 1 and 100".to_string());
+        }
+
+        Ok(Self {
+            path,
+            format,
+            bit_depth,
+            jpeg_quality,
+            tone_mapping,
+        })
+    }
+
+    // Same settings for `<stem>.<suffix>.<extension>` next to the image, without tone mapping
+    pub fn with_suffix(&self, suffix: &str) -> Self {
+        let stem = self
+            .path
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let file_name = format!("{}.{}.{}", stem, suffix, self.format.extension());
+        Self {
+            path: self.path.with_file_name(file_name),
+            tone_mapping: ToneMapping::default(),
+            ..self.clone()
+        }
+    }
+}
+
+// Writes linear radiance, tone mapped for the formats that can't store it as is
+pub fn write_image(
+    settings: &OutputSettings,
+    width: usize,
+    height: usize,
+    pixels: &[Color],
+) -> Result<(), String> {
+    let path = settings.path.as_path();
+    let result = match settings.format {
+        OutputFormat::Png | OutputFormat::Jpeg | OutputFormat::Tiff | OutputFormat::Bmp => {
+            let display: Vec<Color> = pixels
+                .iter()
+                .map(|&color| settings.tone_mapping.apply(color))
+                .collect();
+            return write_ldr(settings, width, height, &display)
+                .map_err(|err| format!("{}: {}", path.display(), err));
+        }
+        OutputFormat::Pfm => write_pfm(path, width, height, pixels),
+        OutputFormat::Hdr => write_hdr(path, width, height, pixels),
+        OutputFormat::Exr => return write_layers(settings, width, height, &[("", pixels)]),
+    };
+
+    result.map_err(|err| format!("{}: {}", path.display(), err))
+}
+
+// Writes named layers in one EXR image, the layer named "" is the main RGB image
+pub fn write_layers(
+    settings: &OutputSettings,
+    width: usize,
+    height: usize,
+    layers: &[(&str, &[Color])],
+) -> Result<(), String> {
+    let path = settings.path.as_path();
+    if settings.format != OutputFormat::Exr {
+        return Err(format!(
+            "{}: only EXR images can hold several layers",
+            path.display()
+        ));
+    }
+
+    let precision = if settings.bit_depth == 16 {
+        ExrPrecision::Half
+    } else {
+        ExrPrecision::Float
+    };
+    write_exr(path, width, height, layers, precision)
+        .map_err(|err| format!("{}: {}", path.display(), err))
+}
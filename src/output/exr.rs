@@ -0,0 +1,187 @@
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+
+use crate::vec3::Color;
+
+const MAGIC: [u8; 4] = [0x76, 0x2f, 0x31, 0x01];
+// Single part scanline file
+const VERSION: u32 = 2;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ExrPrecision {
+    Half,
+    Float,
+}
+
+impl ExrPrecision {
+    fn pixel_type(&self) -> i32 {
+        match self {
+            Self::Half => 1,
+            Self::Float => 2,
+        }
+    }
+
+    fn size(&self) -> usize {
+        match self {
+            Self::Half => 2,
+            Self::Float => 4,
+        }
+    }
+}
+
+// Uncompressed scanline OpenEXR. Each layer is written as R, G and B channels prefixed by the
+// layer name, an empty name gives the default RGB layer.
+pub fn write_exr(
+    path: &Path,
+    width: usize,
+    height: usize,
+    layers: &[(&str, &[Color])],
+    precision: ExrPrecision,
+) -> io::Result<()> {
+    // Channels have to be sorted by name, both in the header and in the pixel data
+    let mut channels = Vec::new();
+    for (layer, (name, _)) in layers.iter().enumerate() {
+        for (component, suffix) in ["R", "G", "B"].iter().enumerate() {
+            let channel_name = if name.is_empty() {
+                suffix.to_string()
+            } else {
+                format!("{}.{}", name, suffix)
+            };
+            channels.push((channel_name, layer, component));
+        }
+    }
+    channels.sort_by(|a, b| a.0.cmp(&b.0));
+
+    let mut header = Vec::new();
+    header.extend_from_slice(&MAGIC);
+    header.extend_from_slice(&VERSION.to_le_bytes());
+
+    let mut chlist = Vec::new();
+    for (name, _, _) in channels.iter() {
+        chlist.extend_from_slice(name.as_bytes());
+        chlist.push(0);
+        chlist.extend_from_slice(&precision.pixel_type().to_le_bytes());
+        // pLinear and reserved bytes
+        chlist.extend_from_slice(&[0, 0, 0, 0]);
+        // x and y sampling
+        chlist.extend_from_slice(&1i32.to_le_bytes());
+        chlist.extend_from_slice(&1i32.to_le_bytes());
+    }
+    chlist.push(0);
+    write_attribute(&mut header, "channels", "chlist", &chlist);
+
+    // No compression
+    write_attribute(&mut header, "compression", "compression", &[0]);
+
+    let mut window = Vec::new();
+    for v in [0, 0, width as i32 - 1, height as i32 - 1] {
+        window.extend_from_slice(&v.to_le_bytes());
+    }
+    write_attribute(&mut header, "dataWindow", "box2i", &window);
+    write_attribute(&mut header, "displayWindow", "box2i", &window);
+
+    // Increasing y
+    write_attribute(&mut header, "lineOrder", "lineOrder", &[0]);
+    write_attribute(
+        &mut header,
+        "pixelAspectRatio",
+        "float",
+        &1f32.to_le_bytes(),
+    );
+    write_attribute(&mut header, "screenWindowCenter", "v2f", &[0; 8]);
+    write_attribute(
+        &mut header,
+        "screenWindowWidth",
+        "float",
+        &1f32.to_le_bytes(),
+    );
+    header.push(0);
+
+    // One scanline per block, each prefixed by its y coordinate and data size
+    let line_size = width * channels.len() * precision.size();
+    let block_size = 8 + line_size;
+    let table_size = 8 * height;
+
+    let mut writer = BufWriter::new(File::create(path)?);
+    writer.write_all(&header)?;
+    for y in 0..height {
+        let offset = (header.len() + table_size + y * block_size) as u64;
+        writer.write_all(&offset.to_le_bytes())?;
+    }
+
+    for y in 0..height {
+        writer.write_all(&(y as i32).to_le_bytes())?;
+        writer.write_all(&(line_size as i32).to_le_bytes())?;
+
+        for (_, layer, component) in channels.iter() {
+            let pixels = &layers[*layer].1[y * width..(y + 1) * width];
+            for color in pixels {
+                let value = match component {
+                    0 => color.x,
+                    1 => color.y,
+                    _ => color.z,
+                } as f32;
+
+                match precision {
+                    ExrPrecision::Half => writer.write_all(&to_half(value).to_le_bytes())?,
+                    ExrPrecision::Float => writer.write_all(&value.to_le_bytes())?,
+                }
+            }
+        }
+    }
+
+    writer.flush()
+}
+
+fn write_attribute(header: &mut Vec<u8>, name: &str, kind: &str, value: &[u8]) {
+    header.extend_from_slice(name.as_bytes());
+    header.push(0);
+    header.extend_from_slice(kind.as_bytes());
+    header.push(0);
+    header.extend_from_slice(&(value.len() as i32).to_le_bytes());
+    header.extend_from_slice(value);
+}
+
+// IEEE 754 binary16 conversion, rounding to nearest even
+fn to_half(value: f32) -> u16 {
+    let bits = value.to_bits();
+    let sign = ((bits >> 16) & 0x8000) as u16;
+    let exponent = ((bits >> 23) & 0xff) as i32;
+    let mantissa = bits & 0x7f_ffff;
+
+    // Infinity and NaN
+    if exponent == 0xff {
+        let nan = if mantissa != 0 { 0x200 } else { 0 };
+        return sign | 0x7c00 | nan;
+    }
+
+    let exponent = exponent - 127 + 15;
+    if exponent >= 0x1f {
+        return sign | 0x7c00;
+    }
+
+    // Subnormal halves, the implicit leading bit becomes explicit
+    if exponent <= 0 {
+        if exponent < -10 {
+            return sign;
+        }
+        let mantissa = mantissa | 0x80_0000;
+        let shift = (14 - exponent) as u32;
+        return sign | round_shift(mantissa, shift) as u16;
+    }
+
+    // A carry out of the mantissa correctly bumps the exponent
+    sign | round_shift((exponent as u32) << 23 | mantissa, 13) as u16
+}
+
+fn round_shift(value: u32, shift: u32) -> u32 {
+    let shifted = value >> shift;
+    let rest = value & ((1 << shift) - 1);
+    let halfway = 1 << (shift - 1);
+    if rest > halfway || (rest == halfway && shifted & 1 == 1) {
+        shifted + 1
+    } else {
+        shifted
+    }
+}
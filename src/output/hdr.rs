@@ -0,0 +1,107 @@
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+
+use crate::vec3::Color;
+
+// Shortest and longest scanlines the run length encoding can be used for
+const MIN_RLE_WIDTH: usize = 8;
+const MAX_RLE_WIDTH: usize = 0x7fff;
+const MIN_RUN_LENGTH: usize = 4;
+const MAX_CHUNK_LENGTH: usize = 127;
+
+// Radiance RGBE image, using the run length encoded scanline format when the width allows it
+pub fn write_hdr(path: &Path, width: usize, height: usize, pixels: &[Color]) -> io::Result<()> {
+    let mut writer = BufWriter::new(File::create(path)?);
+    write!(
+        writer,
+        "#?RADIANCE\nFORMAT=32-bit_rle_rgbe\n\n-Y {} +X {}\n",
+        height, width
+    )?;
+
+    let mut components = vec![0u8; width];
+    for scanline in pixels.chunks(width).take(height) {
+        let rgbe: Vec<[u8; 4]> = scanline.iter().map(|&color| to_rgbe(color)).collect();
+
+        if !(MIN_RLE_WIDTH..=MAX_RLE_WIDTH).contains(&width) {
+            for pixel in rgbe.iter() {
+                writer.write_all(pixel)?;
+            }
+            continue;
+        }
+
+        writer.write_all(&[2, 2, (width >> 8) as u8, (width & 0xff) as u8])?;
+        for i in 0..4 {
+            for (component, pixel) in components.iter_mut().zip(rgbe.iter()) {
+                *component = pixel[i];
+            }
+            write_rle(&mut writer, &components)?;
+        }
+    }
+
+    writer.flush()
+}
+
+// Shared exponent encoding, the mantissas are the components scaled by the largest one
+fn to_rgbe(color: Color) -> [u8; 4] {
+    let r = color.x.max(0.0);
+    let g = color.y.max(0.0);
+    let b = color.z.max(0.0);
+    let v = r.max(g).max(b);
+    if v < 1e-32 {
+        return [0, 0, 0, 0];
+    }
+
+    let mut exponent = v.log2().floor() as i32 + 1;
+    // Rounding in log2 can leave the mantissa at exactly 1
+    if v / 2f64.powi(exponent) >= 1.0 {
+        exponent += 1;
+    }
+    let scale = 256.0 / 2f64.powi(exponent);
+
+    [
+        (r * scale) as u8,
+        (g * scale) as u8,
+        (b * scale) as u8,
+        (exponent + 128).clamp(0, 255) as u8,
+    ]
+}
+
+// Runs of identical bytes are written as (128 + length, value), everything else as
+// (length, bytes...)
+fn write_rle<W: Write>(writer: &mut W, data: &[u8]) -> io::Result<()> {
+    let mut current = 0;
+    while current < data.len() {
+        // Find the next run long enough to be worth encoding
+        let mut run_start = current;
+        let mut run_length = 0;
+        while run_start < data.len() {
+            run_length = 1;
+            while run_start + run_length < data.len()
+                && run_length < MAX_CHUNK_LENGTH
+                && data[run_start + run_length] == data[run_start]
+            {
+                run_length += 1;
+            }
+            if run_length >= MIN_RUN_LENGTH {
+                break;
+            }
+            run_start += run_length;
+        }
+
+        // Literal bytes before the run
+        while current < run_start {
+            let count = std::cmp::min(run_start - current, MAX_CHUNK_LENGTH);
+            writer.write_all(&[count as u8])?;
+            writer.write_all(&data[current..current + count])?;
+            current += count;
+        }
+
+        if run_length >= MIN_RUN_LENGTH {
+            writer.write_all(&[128 + run_length as u8, data[run_start]])?;
+            current += run_length;
+        }
+    }
+
+    Ok(())
+}
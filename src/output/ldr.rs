@@ -0,0 +1,62 @@
+use std::fs::File;
+use std::io::BufWriter;
+
+use image::codecs::jpeg::JpegEncoder;
+use image::{ImageBuffer, ImageFormat, ImageResult, Rgb};
+
+use super::{OutputFormat, OutputSettings};
+use crate::math::clamp;
+use crate::vec3::Color;
+
+// 8 or 16 bit images through the image crate, `pixels` are display values in [0, 1]
+pub fn write_ldr(
+    settings: &OutputSettings,
+    width: usize,
+    height: usize,
+    pixels: &[Color],
+) -> ImageResult<()> {
+    let path = settings.path.as_path();
+    let format = match settings.format {
+        OutputFormat::Png => ImageFormat::Png,
+        OutputFormat::Tiff => ImageFormat::Tiff,
+        OutputFormat::Bmp => ImageFormat::Bmp,
+        OutputFormat::Jpeg => {
+            let img = to_rgb8(width, height, pixels);
+            let mut writer = BufWriter::new(File::create(path)?);
+            let mut encoder = JpegEncoder::new_with_quality(&mut writer, settings.jpeg_quality);
+            return encoder.encode_image(&img);
+        }
+        _ => unreachable!("{:?} is not a low dynamic range format", settings.format),
+    };
+
+    if settings.bit_depth == 16 {
+        let mut img: ImageBuffer<Rgb<u16>, Vec<u16>> =
+            ImageBuffer::new(width as u32, height as u32);
+        for (pixel, &color) in img.pixels_mut().zip(pixels.iter()) {
+            *pixel = Rgb([
+                quantize(color.x, u16::MAX as f64) as u16,
+                quantize(color.y, u16::MAX as f64) as u16,
+                quantize(color.z, u16::MAX as f64) as u16,
+            ]);
+        }
+        img.save_with_format(path, format)
+    } else {
+        to_rgb8(width, height, pixels).save_with_format(path, format)
+    }
+}
+
+fn to_rgb8(width: usize, height: usize, pixels: &[Color]) -> ImageBuffer<Rgb<u8>, Vec<u8>> {
+    let mut img = ImageBuffer::new(width as u32, height as u32);
+    for (pixel, &color) in img.pixels_mut().zip(pixels.iter()) {
+        *pixel = Rgb([
+            quantize(color.x, u8::MAX as f64) as u8,
+            quantize(color.y, u8::MAX as f64) as u8,
+            quantize(color.z, u8::MAX as f64) as u8,
+        ]);
+    }
+    img
+}
+
+fn quantize(value: f64, max: f64) -> f64 {
+    (clamp(value, 0.0, 1.0) * max).round()
+}
@@ -0,0 +1,21 @@
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+
+use crate::vec3::Color;
+
+// Portable float map, little endian (negative scale) with scanlines stored bottom to top
+pub fn write_pfm(path: &Path, width: usize, height: usize, pixels: &[Color]) -> io::Result<()> {
+    let mut writer = BufWriter::new(File::create(path)?);
+    write!(writer, "PF\n{} {}\n-1.0\n", width, height)?;
+
+    for y in (0..height).rev() {
+        for color in &pixels[y * width..(y + 1) * width] {
+            for c in [color.x, color.y, color.z] {
+                writer.write_all(&(c as f32).to_le_bytes())?;
+            }
+        }
+    }
+
+    writer.flush()
+}
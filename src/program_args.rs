@@ -1,4 +1,139 @@
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Command {
+    // Render the scene and save the image
+    Render,
+    // Check the config and build the scene without rendering
+    Validate,
+    // Print statistics about the scene
+    Info,
+    // Render without saving and report the timings
+    Bench,
+}
+
 pub struct ProgramArgs {
+    pub command: Command,
     pub file_path: String,
     pub job_count: usize,
+    pub output: Option<String>,
+    pub force: bool,
+    // `key.path=value` overrides applied to the config before it is deserialized
+    pub overrides: Vec<(String, String)>,
+}
+
+pub fn usage(program: &str) -> String {
+    format!(
+        "Usage: {program} [render|validate|info|bench] config_file.json [job_count] [options]
+
+Commands:
+    render      Render the scene and save the image (default)
+    validate    Check the config and build the scene without rendering
+    info        Print statistics about the scene
+    bench       Render without saving and report the timings
+
+Options:
+    -o, --output FILE     Output image, the format follows the extension
+    -f, --force           Overwrite the output image if it exists
+    -t, --threads N       Number of render threads
+        --spp N           Samples per pixel
+        --width N         Image width
+        --height N        Image height
+        --max-depth N     Maximum number of bounces
+        --seed N          Random seed, renders with the same seed are identical
+        --set KEY=VALUE   Override a config value, KEY is a dotted path like eye.x or objects.0.radius
+    -h, --help            Print this message",
+        program = program
+    )
+}
+
+pub fn parse_args(args: &[String]) -> Result<ProgramArgs, String> {
+    let program = args.first().map(String::as_str).unwrap_or("rustracer");
+    let mut iter = args.iter().skip(1).peekable();
+
+    let command = match iter.peek().map(|arg| arg.as_str()) {
+        Some("render") => Some(Command::Render),
+        Some("validate") => Some(Command::Validate),
+        Some("info") => Some(Command::Info),
+        Some("bench") => Some(Command::Bench),
+        _ => None,
+    };
+    if command.is_some() {
+        iter.next();
+    }
+
+    let mut positionals = Vec::new();
+    let mut output = None;
+    let mut force = false;
+    let mut job_count = None;
+    let mut overrides = Vec::new();
+    while let Some(arg) = iter.next() {
+        let mut value = || {
+            iter.next()
+                .cloned()
+                .ok_or_else(|| format!("Missing value after {}", arg))
+        };
+
+        // Shorthands for the most common config overrides
+        let config_key = match arg.as_str() {
+            "--spp" => Some("sample_per_pixel"),
+            "--width" => Some("img_width"),
+            "--height" => Some("img_height"),
+            "--max-depth" => Some("max_depth"),
+            "--seed" => Some("seed"),
+            _ => None,
+        };
+        if let Some(key) = config_key {
+            let value = value()?;
+            value
+                .parse::<u64>()
+                .map_err(|_| format!("{} must be an unsigned number", arg))?;
+            overrides.push((key.to_string(), value));
+            continue;
+        }
+
+        match arg.as_str() {
+            "-h" | "--help" => return Err(usage(program)),
+            "-o" | "--output" => output = Some(value()?),
+            "-f" | "--force" => force = true,
+            "-t" | "--threads" => {
+                let count = value()?;
+                job_count = Some(parse_count(&count, arg)?);
+            }
+            "--set" => {
+                let assignment = value()?;
+                let (key, value) = assignment
+                    .split_once('=')
+                    .ok_or_else(|| format!("--set expects KEY=VALUE, got \"{}\"", assignment))?;
+                overrides.push((key.to_string(), value.to_string()));
+            }
+            _ if arg.starts_with('-') => {
+                return Err(format!("Unknown option {}\n\n{}", arg, usage(program)))
+            }
+            _ => positionals.push(arg.clone()),
+        }
+    }
+
+    // The job count can still be given after the config path
+    let file_path = match positionals.as_slice() {
+        [file_path] => file_path.clone(),
+        [file_path, count] if job_count.is_none() => {
+            job_count = Some(parse_count(count, "Job count")?);
+            file_path.clone()
+        }
+        _ => return Err(usage(program)),
+    };
+
+    Ok(ProgramArgs {
+        command: command.unwrap_or(Command::Render),
+        file_path,
+        job_count: job_count.unwrap_or(0),
+        output,
+        force,
+        overrides,
+    })
+}
+
+fn parse_count(value: &str, name: &str) -> Result<usize, String> {
+    value
+        .parse()
+        .map_err(|_| format!("{} must be an unsigned number", name))
 }
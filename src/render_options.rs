@@ -1,4 +1,10 @@
-use crate::scene::Background;
+use crate::adaptive::AdaptiveSampling;
+use crate::aov::Aov;
+use crate::filter::Filter;
+use crate::integrator::IntegratorKind;
+use crate::sampler::SamplerKind;
+use crate::scene::{Background, Config, Scene};
+use crate::tiles::TileOrder;
 
 #[derive(Clone)]
 pub struct RenderOptions {
@@ -7,25 +13,37 @@ pub struct RenderOptions {
     pub img_height: usize,
     pub sample_per_pixel: u32,
     pub max_depth: u32,
+    pub roulette_depth: u32,
     pub background: Background,
+    pub tile_size: usize,
+    pub tile_order: TileOrder,
+    pub seed: u64,
+    pub sampler: SamplerKind,
+    pub adaptive: Option<AdaptiveSampling>,
+    pub filter: Filter,
+    pub integrator: IntegratorKind,
+    pub aovs: Vec<Aov>,
 }
 
 impl RenderOptions {
-    pub fn new(
-        progress_tick_rate: usize,
-        img_width: usize,
-        img_height: usize,
-        sample_per_pixel: u32,
-        max_depth: u32,
-        background: Background,
-    ) -> Self {
+    // The image size, sample count and depth come from the scene, the rest from the config
+    pub fn new(cfg: &Config, scene: &Scene) -> Self {
         Self {
-            progress_tick_rate,
-            img_width,
-            img_height,
-            sample_per_pixel,
-            max_depth,
-            background,
+            progress_tick_rate: cfg.progress_tick_rate,
+            img_width: scene.img_width,
+            img_height: scene.img_height,
+            sample_per_pixel: scene.sample_per_pixel,
+            max_depth: scene.max_depth,
+            roulette_depth: cfg.roulette_depth,
+            background: cfg.background.clone(),
+            tile_size: cfg.tile_size,
+            tile_order: cfg.tile_order,
+            seed: cfg.seed,
+            sampler: cfg.sampler,
+            adaptive: cfg.adaptive.clone(),
+            filter: cfg.filter,
+            integrator: cfg.integrator,
+            aovs: cfg.aovs.clone(),
         }
     }
 }
@@ -0,0 +1,129 @@
+pub mod halton;
+pub mod independent;
+pub mod sobol;
+pub mod stratified;
+
+use serde::Deserialize;
+
+use crate::vec3::Vec3;
+
+pub use self::halton::HaltonSampler;
+pub use self::independent::IndependentSampler;
+pub use self::sobol::SobolSampler;
+pub use self::stratified::StratifiedSampler;
+
+// Source of the sample values of a pixel. Every sample draws its dimensions in the same order
+// (pixel position, lens position, time, then the bounces), which lets the low discrepancy
+// samplers distribute each dimension well over the samples of a pixel.
+pub trait Sampler {
+    // Moves to sample `index` of `pixel`, the next dimension drawn is the first one
+    fn start_sample(&mut self, pixel: (usize, usize), index: u32);
+    fn get_1d(&mut self) -> f64;
+    fn get_2d(&mut self) -> (f64, f64);
+}
+
+#[derive(Deserialize, Clone, Copy, Debug, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum SamplerKind {
+    // Uniform random values
+    Independent,
+    // Jittered strata
+    Stratified,
+    // Halton sequence, randomized per pixel
+    Halton,
+    // Owen scrambled Sobol (0, 2)-sequence padded over every dimension pair
+    #[default]
+    Sobol,
+}
+
+impl SamplerKind {
+    pub fn create(&self, sample_per_pixel: u32, seed: u64) -> Box<dyn Sampler> {
+        match self {
+            Self::Independent => Box::new(IndependentSampler::new(seed)),
+            Self::Stratified => Box::new(StratifiedSampler::new(sample_per_pixel, seed)),
+            Self::Halton => Box::new(HaltonSampler::new(seed)),
+            Self::Sobol => Box::new(SobolSampler::new(sample_per_pixel, seed)),
+        }
+    }
+}
+
+// Kensler's hashed permutation, the element at `index` of a random permutation of `0..len`
+pub fn permutation_element(index: u32, len: u32, seed: u32) -> u32 {
+    let mut w = len - 1;
+    w |= w >> 1;
+    w |= w >> 2;
+    w |= w >> 4;
+    w |= w >> 8;
+    w |= w >> 16;
+
+    let p = seed;
+    let mut i = index;
+    loop {
+        i ^= p;
+        i = i.wrapping_mul(0xe170893d);
+        i ^= p >> 16;
+        i ^= (i & w) >> 4;
+        i ^= p >> 8;
+        i = i.wrapping_mul(0x0929eb3f);
+        i ^= p >> 23;
+        i ^= (i & w) >> 1;
+        i = i.wrapping_mul(1 | p >> 27);
+        i = i.wrapping_mul(0x6935fa69);
+        i ^= (i & w) >> 11;
+        i = i.wrapping_mul(0x74dcb303);
+        i ^= (i & w) >> 2;
+        i = i.wrapping_mul(0x9e501cc3);
+        i ^= (i & w) >> 2;
+        i = i.wrapping_mul(0xc860a3df);
+        i &= w;
+        i ^= i >> 5;
+        if i < len {
+            break;
+        }
+    }
+
+    ((i as u64 + p as u64) % len as u64) as u32
+}
+
+// Largest f64 below 1, samples are in [0, 1)
+pub const ONE_MINUS_EPSILON: f64 = 1.0 - f64::EPSILON / 2.0;
+
+// Concentric mapping of the unit square on the unit disk, keeps the strata shapes
+pub fn sample_unit_disk(u: (f64, f64)) -> Vec3 {
+    let x = 2.0 * u.0 - 1.0;
+    let y = 2.0 * u.1 - 1.0;
+    if x == 0.0 && y == 0.0 {
+        return Vec3::zero();
+    }
+
+    let (r, theta) = if x.abs() > y.abs() {
+        (x, std::f64::consts::FRAC_PI_4 * (y / x))
+    } else {
+        (
+            y,
+            std::f64::consts::FRAC_PI_2 - std::f64::consts::FRAC_PI_4 * (x / y),
+        )
+    };
+
+    Vec3::new(r * theta.cos(), r * theta.sin(), 0.0)
+}
+
+// Cosine weighted direction around the z axis, projected from the unit disk (Malley's method)
+pub fn sample_cosine_hemisphere(u: (f64, f64)) -> Vec3 {
+    let d = sample_unit_disk(u);
+    let z = (1.0 - d.x * d.x - d.y * d.y).max(0.0).sqrt();
+    Vec3::new(d.x, d.y, z)
+}
+
+// Uniformly distributed direction
+pub fn sample_unit_sphere(u: (f64, f64)) -> Vec3 {
+    let z = 1.0 - 2.0 * u.0;
+    let r = (1.0 - z * z).max(0.0).sqrt();
+    let phi = 2.0 * std::f64::consts::PI * u.1;
+    Vec3::new(r * phi.cos(), r * phi.sin(), z)
+}
+
+// Uniformly distributed point inside the unit sphere
+pub fn sample_in_unit_sphere(u: (f64, f64), radius: f64) -> Vec3 {
+    sample_unit_sphere(u) * radius.cbrt()
+}
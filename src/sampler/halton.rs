@@ -0,0 +1,100 @@
+use rand::Rng;
+
+use super::{permutation_element, Sampler, ONE_MINUS_EPSILON};
+use crate::rng::{hash_values, Pcg32};
+
+// Dimensions past the last prime base fall back to random values
+const PRIME_COUNT: usize = 256;
+
+// Halton sequence, one prime base per dimension. The digits are randomly permuted for each
+// pixel and dimension, which decorrelates the pixels and spreads the samples of the large bases
+// that would otherwise all start close to 0.
+pub struct HaltonSampler {
+    primes: Vec<u32>,
+    seed: u64,
+    pixel_hash: u64,
+    index: u32,
+    dimension: usize,
+    rng: Pcg32,
+}
+
+impl HaltonSampler {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            primes: primes(PRIME_COUNT),
+            seed,
+            pixel_hash: 0,
+            index: 0,
+            dimension: 0,
+            rng: Pcg32::new(seed, 0),
+        }
+    }
+
+    fn next(&mut self) -> f64 {
+        let dimension = self.dimension;
+        self.dimension += 1;
+
+        match self.primes.get(dimension) {
+            Some(&base) => {
+                let hash = hash_values(&[self.pixel_hash, dimension as u64, self.seed]);
+                scrambled_radical_inverse(self.index, base, hash)
+            }
+            None => self.rng.gen(),
+        }
+    }
+}
+
+impl Sampler for HaltonSampler {
+    fn start_sample(&mut self, pixel: (usize, usize), index: u32) {
+        self.pixel_hash = hash_values(&[pixel.0 as u64, pixel.1 as u64]);
+        self.index = index;
+        self.dimension = 0;
+        self.rng = Pcg32::for_sample(self.seed, self.pixel_hash, index as u64);
+    }
+
+    fn get_1d(&mut self) -> f64 {
+        self.next()
+    }
+
+    fn get_2d(&mut self) -> (f64, f64) {
+        (self.next(), self.next())
+    }
+}
+
+// Mirrors the digits of `index` around the radix point after permuting them. The trailing digits
+// past the last one of `index` would all be permuted zeros, they are replaced by a random value.
+fn scrambled_radical_inverse(index: u32, base: u32, hash: u64) -> f64 {
+    let inv_base = 1.0 / base as f64;
+    let mut factor = inv_base;
+    let mut value = 0.0;
+    let mut remaining = index;
+    let mut digit_index = 0;
+
+    while remaining > 0 {
+        let digit = remaining % base;
+        let digit_hash = hash_values(&[hash, digit_index]) as u32;
+        value += permutation_element(digit, base, digit_hash) as f64 * factor;
+        remaining /= base;
+        factor *= inv_base;
+        digit_index += 1;
+    }
+
+    let tail = (hash_values(&[hash, digit_index]) >> 11) as f64 / (1u64 << 53) as f64;
+    (value + tail * factor * base as f64).min(ONE_MINUS_EPSILON)
+}
+
+fn primes(count: usize) -> Vec<u32> {
+    let mut primes = Vec::with_capacity(count);
+    let mut candidate = 2;
+    while primes.len() < count {
+        if primes
+            .iter()
+            .take_while(|&&p| p * p <= candidate)
+            .all(|&p| candidate % p != 0)
+        {
+            primes.push(candidate);
+        }
+        candidate += 1;
+    }
+    primes
+}
@@ -0,0 +1,33 @@
+use rand::Rng;
+
+use super::Sampler;
+use crate::rng::{hash_values, Pcg32};
+
+pub struct IndependentSampler {
+    seed: u64,
+    rng: Pcg32,
+}
+
+impl IndependentSampler {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            seed,
+            rng: Pcg32::new(seed, 0),
+        }
+    }
+}
+
+impl Sampler for IndependentSampler {
+    fn start_sample(&mut self, pixel: (usize, usize), index: u32) {
+        let pixel_hash = hash_values(&[pixel.0 as u64, pixel.1 as u64]);
+        self.rng = Pcg32::for_sample(self.seed, pixel_hash, index as u64);
+    }
+
+    fn get_1d(&mut self) -> f64 {
+        self.rng.gen()
+    }
+
+    fn get_2d(&mut self) -> (f64, f64) {
+        (self.rng.gen(), self.rng.gen())
+    }
+}
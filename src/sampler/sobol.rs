@@ -0,0 +1,95 @@
+use super::{permutation_element, Sampler, ONE_MINUS_EPSILON};
+use crate::rng::hash_values;
+
+// Owen scrambled Sobol points, using only the first two dimensions of the sequence (a (0, 2)
+// sequence) for every dimension pair. The sample order is shuffled per pixel and dimension so
+// the pairs aren't correlated with each other.
+pub struct SobolSampler {
+    sample_count: u32,
+    // Generator matrix of the second Sobol dimension, the first one is the identity
+    matrix: [u32; 32],
+    seed: u64,
+    pixel_hash: u64,
+    index: u32,
+    dimension: u64,
+}
+
+impl SobolSampler {
+    pub fn new(sample_per_pixel: u32, seed: u64) -> Self {
+        let mut matrix = [0; 32];
+        matrix[0] = 1 << 31;
+        for i in 1..32 {
+            matrix[i] = matrix[i - 1] ^ (matrix[i - 1] >> 1);
+        }
+
+        Self {
+            sample_count: std::cmp::max(sample_per_pixel, 1),
+            matrix,
+            seed,
+            pixel_hash: 0,
+            index: 0,
+            dimension: 0,
+        }
+    }
+
+    // Shuffled sample index and the scrambling seeds of the next dimension pair
+    fn next(&mut self) -> (u32, u64) {
+        let hash = hash_values(&[self.pixel_hash, self.dimension, self.seed]);
+        self.dimension += 1;
+        let index = permutation_element(
+            self.index % self.sample_count,
+            self.sample_count,
+            hash as u32,
+        );
+        (index, hash)
+    }
+
+    fn second_dimension(&self, index: u32) -> u32 {
+        let mut value = 0;
+        let mut bits = index;
+        let mut column = 0;
+        while bits != 0 {
+            if bits & 1 == 1 {
+                value ^= self.matrix[column];
+            }
+            bits >>= 1;
+            column += 1;
+        }
+        value
+    }
+}
+
+impl Sampler for SobolSampler {
+    fn start_sample(&mut self, pixel: (usize, usize), index: u32) {
+        self.pixel_hash = hash_values(&[pixel.0 as u64, pixel.1 as u64]);
+        self.index = index;
+        self.dimension = 0;
+    }
+
+    fn get_1d(&mut self) -> f64 {
+        let (index, hash) = self.next();
+        to_unit(owen_scramble(index.reverse_bits(), (hash >> 32) as u32))
+    }
+
+    fn get_2d(&mut self) -> (f64, f64) {
+        let (index, hash) = self.next();
+        let x = owen_scramble(index.reverse_bits(), (hash >> 32) as u32);
+        let y = owen_scramble(self.second_dimension(index), hash_values(&[hash]) as u32);
+        (to_unit(x), to_unit(y))
+    }
+}
+
+// Hash based nested uniform scrambling (Burley, "Practical Hash-based Owen Scrambling")
+fn owen_scramble(value: u32, seed: u32) -> u32 {
+    let mut x = value.reverse_bits();
+    x ^= x.wrapping_mul(0x3d20adea);
+    x = x.wrapping_add(seed);
+    x = x.wrapping_mul((seed >> 16) | 1);
+    x ^= x.wrapping_mul(0x05526c56);
+    x ^= x.wrapping_mul(0x53a22864);
+    x.reverse_bits()
+}
+
+fn to_unit(value: u32) -> f64 {
+    (value as f64 / (1u64 << 32) as f64).min(ONE_MINUS_EPSILON)
+}
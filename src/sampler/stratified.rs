@@ -0,0 +1,68 @@
+use rand::Rng;
+
+use super::{permutation_element, Sampler, ONE_MINUS_EPSILON};
+use crate::rng::{hash_values, Pcg32};
+
+// Each dimension is split in one stratum per sample (a grid for 2D dimensions). The samples
+// of a pixel take the strata in a different random order for every dimension and are jittered
+// inside them.
+pub struct StratifiedSampler {
+    sample_count: u32,
+    // Grid used for the 2D dimensions, it can have more cells than there are samples
+    x_strata: u32,
+    y_strata: u32,
+    seed: u64,
+    pixel_hash: u64,
+    index: u32,
+    dimension: u64,
+    rng: Pcg32,
+}
+
+impl StratifiedSampler {
+    pub fn new(sample_per_pixel: u32, seed: u64) -> Self {
+        let sample_count = std::cmp::max(sample_per_pixel, 1);
+        let x_strata = (sample_count as f64).sqrt().ceil() as u32;
+        let y_strata = sample_count.div_ceil(x_strata);
+
+        Self {
+            sample_count,
+            x_strata,
+            y_strata,
+            seed,
+            pixel_hash: 0,
+            index: 0,
+            dimension: 0,
+            rng: Pcg32::new(seed, 0),
+        }
+    }
+
+    fn stratum(&mut self, count: u32) -> u32 {
+        let hash = hash_values(&[self.pixel_hash, self.dimension, self.seed]);
+        self.dimension += 1;
+        permutation_element(self.index % count, count, hash as u32)
+    }
+}
+
+impl Sampler for StratifiedSampler {
+    fn start_sample(&mut self, pixel: (usize, usize), index: u32) {
+        self.pixel_hash = hash_values(&[pixel.0 as u64, pixel.1 as u64]);
+        self.index = index;
+        self.dimension = 0;
+        self.rng = Pcg32::for_sample(self.seed, self.pixel_hash, index as u64);
+    }
+
+    fn get_1d(&mut self) -> f64 {
+        let stratum = self.stratum(self.sample_count);
+        let value = (stratum as f64 + self.rng.gen::<f64>()) / self.sample_count as f64;
+        value.min(ONE_MINUS_EPSILON)
+    }
+
+    fn get_2d(&mut self) -> (f64, f64) {
+        let stratum = self.stratum(self.x_strata * self.y_strata);
+        let x = stratum % self.x_strata;
+        let y = stratum / self.x_strata;
+        let u = (x as f64 + self.rng.gen::<f64>()) / self.x_strata as f64;
+        let v = (y as f64 + self.rng.gen::<f64>()) / self.y_strata as f64;
+        (u.min(ONE_MINUS_EPSILON), v.min(ONE_MINUS_EPSILON))
+    }
+}
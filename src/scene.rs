@@ -1,12 +1,24 @@
 pub mod background;
 pub mod camera;
 pub mod config;
+pub mod generator;
+pub mod material_config;
+pub mod object;
 
 pub use self::background::Background;
 pub use self::camera::Camera;
 pub use self::config::Config;
 
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use self::object::SceneAssets;
+use crate::accelerator::Accelerator;
+use crate::axis_aligned_bb::AxisAlignedBB;
 use crate::hittable::HittableList;
+use crate::light::LightList;
 
 pub struct Scene {
     pub img_width: usize,
@@ -14,11 +26,41 @@ pub struct Scene {
     pub sample_per_pixel: u32,
     pub max_depth: u32,
     camera: Camera,
-    objects: std::sync::Arc<HittableList>,
+    objects: Arc<Accelerator>,
+    lights: Arc<LightList>,
+    // Index of every material by address, in the order the objects use them
+    material_ids: HashMap<usize, usize>,
+    // Index of every top level object by address, starting at 1
+    object_ids: HashMap<usize, usize>,
+    stats: SceneStats,
+}
+
+pub struct SceneStats {
+    pub object_count: usize,
+    pub primitive_count: usize,
+    pub light_count: usize,
+    pub bounds: Option<AxisAlignedBB>,
+    // Time spent creating the objects and loading files, then building the accelerator
+    pub load_time: Duration,
+    pub build_time: Duration,
+}
+
+impl fmt::Display for SceneStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} objects, {} primitives, {} lights, loaded in {:.2?}, built in {:.2?}",
+            self.object_count,
+            self.primitive_count,
+            self.light_count,
+            self.load_time,
+            self.build_time
+        )
+    }
 }
 
 impl Scene {
-    pub fn new(config: &Config, objects: HittableList) -> Self {
+    pub fn new(config: &Config) -> Result<Self, String> {
         let camera = Camera::new(
             config.eye,
             config.look_at,
@@ -31,20 +73,91 @@ impl Scene {
             config.time1,
         );
 
-        Self {
+        let load_start = Instant::now();
+        let mut objects = match config.generator {
+            Some(generator) => generator.generate(config.seed),
+            None => HittableList::new(),
+        };
+
+        let mut assets = SceneAssets::new(config)?;
+        for object in config.objects.iter() {
+            for hittable in object.build(&mut assets)? {
+                objects.add(Arc::new(hittable));
+            }
+        }
+
+        let lights = LightList::new(&objects)?;
+        let mut materials = Vec::new();
+        for object in objects.objects.iter() {
+            object.collect_materials(&mut materials);
+        }
+        let mut material_ids = HashMap::new();
+        for material in materials {
+            let next_id = material_ids.len();
+            material_ids
+                .entry(material as *const _ as usize)
+                .or_insert(next_id);
+        }
+        let object_ids = objects
+            .objects
+            .iter()
+            .enumerate()
+            .map(|(i, object)| (Arc::as_ptr(object) as usize, i + 1))
+            .collect();
+
+        let stats = SceneStats {
+            object_count: objects.objects.len(),
+            primitive_count: objects
+                .objects
+                .iter()
+                .map(|object| object.primitive_count())
+                .sum(),
+            light_count: lights.len(),
+            bounds: objects.bounding_box(config.time0, config.time1),
+            load_time: load_start.elapsed(),
+            build_time: Duration::default(),
+        };
+
+        let build_start = Instant::now();
+        let objects = Accelerator::new(config.accelerator, objects, config.time0, config.time1);
+
+        Ok(Self {
             img_width: config.img_width,
             img_height: config.img_height,
             sample_per_pixel: config.sample_per_pixel,
             max_depth: config.max_depth,
             camera,
-            objects: std::sync::Arc::new(objects),
-        }
+            objects: Arc::new(objects),
+            lights: Arc::new(lights),
+            material_ids,
+            object_ids,
+            stats: SceneStats {
+                build_time: build_start.elapsed(),
+                ..stats
+            },
+        })
     }
 
-    pub fn get_objects(&self) -> std::sync::Arc<HittableList> {
+    pub fn get_objects(&self) -> Arc<Accelerator> {
         self.objects.clone()
     }
 
+    pub fn get_lights(&self) -> Arc<LightList> {
+        self.lights.clone()
+    }
+
+    pub fn material_ids(&self) -> HashMap<usize, usize> {
+        self.material_ids.clone()
+    }
+
+    pub fn object_ids(&self) -> HashMap<usize, usize> {
+        self.object_ids.clone()
+    }
+
+    pub fn get_stats(&self) -> &SceneStats {
+        &self.stats
+    }
+
     pub fn get_camera(&self) -> Camera {
         self.camera.clone()
     }
@@ -1,7 +1,6 @@
-use rand::{thread_rng, Rng};
-
 use crate::math;
 use crate::ray::Ray;
+use crate::sampler::sample_unit_disk;
 use crate::vec3::Vec3;
 
 #[derive(Clone)]
@@ -12,7 +11,6 @@ pub struct Camera {
     lower_left_corner: Vec3,
     u: Vec3,
     v: Vec3,
-    w: Vec3,
     lens_radius: f64,
     t0: f64,
     t1: f64,
@@ -50,15 +48,15 @@ impl Camera {
             lower_left_corner: eye - horizontal / 2.0 + vertical / 2.0 - focus_dist * w,
             u,
             v,
-            w,
             lens_radius: aperture / 2.0,
             t0,
             t1,
         }
     }
 
-    pub fn get_ray(&self, s: f64, t: f64) -> Ray {
-        let rd = self.lens_radius * Vec3::random_vector_in_unit_disk();
+    // `u_lens` picks the point on the lens and `u_time` the time of the ray
+    pub fn get_ray(&self, s: f64, t: f64, u_lens: (f64, f64), u_time: f64) -> Ray {
+        let rd = self.lens_radius * sample_unit_disk(u_lens);
         let offset = self.u * rd.x + self.v * rd.y;
         let dir = (self.lower_left_corner + s * self.horizontal
             - t * self.vertical
@@ -69,7 +67,7 @@ impl Camera {
         Ray::new(
             self.origin + offset,
             dir,
-            thread_rng().gen_range(self.t0..=self.t1),
+            self.t0 + u_time * (self.t1 - self.t0),
         )
     }
 }
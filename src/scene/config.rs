@@ -1,14 +1,34 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use crate::accelerator::AcceleratorKind;
+use crate::adaptive::AdaptiveSampling;
+use crate::aov::{Aov, AovOutput};
+use crate::filter::Filter;
+use crate::integrator::IntegratorKind;
+use crate::output::OutputFormat;
+use crate::sampler::SamplerKind;
 use crate::scene::background::Background;
+use crate::scene::generator::Generator;
+use crate::scene::material_config::{MaterialConfig, TextureConfig};
+use crate::scene::object::ObjectConfig;
+use crate::tiles::TileOrder;
+use crate::tonemap::ToneMap;
 use crate::vec3::Vec3;
 use serde::Deserialize;
+use serde_json::Value;
 
 #[derive(Deserialize, Clone)]
+#[serde(deny_unknown_fields)]
 pub struct Config {
     pub progress_tick_rate: usize,
     pub img_width: usize,
     pub img_height: usize,
     pub sample_per_pixel: u32,
     pub max_depth: u32,
+    // Paths can be randomly terminated after this many bounces
+    #[serde(default = "default_roulette_depth")]
+    pub roulette_depth: u32,
     pub v_fov: f64,
     pub eye: Vec3,
     pub look_at: Vec3,
@@ -18,4 +38,194 @@ pub struct Config {
     pub time0: f64,
     pub time1: f64,
     pub background: Background,
+    // Width and height in pixels of the tiles the image is split into for rendering
+    #[serde(default = "default_tile_size")]
+    pub tile_size: usize,
+    #[serde(default)]
+    pub tile_order: TileOrder,
+    #[serde(default)]
+    pub accelerator: AcceleratorKind,
+    // Output image path, relative to the working directory
+    #[serde(default)]
+    pub output: Option<PathBuf>,
+    #[serde(default)]
+    pub output_format: Option<OutputFormat>,
+    // Bits per channel, for the formats supporting more than one
+    #[serde(default)]
+    pub bit_depth: Option<u32>,
+    #[serde(default = "default_jpeg_quality")]
+    pub jpeg_quality: u8,
+    #[serde(default)]
+    pub tone_map: ToneMap,
+    // Exposure adjustment in stops, applied before tone mapping
+    #[serde(default)]
+    pub exposure: f64,
+    #[serde(default)]
+    pub generator: Option<Generator>,
+    // Seed of every random choice, renders with the same seed are identical
+    #[serde(default)]
+    pub seed: u64,
+    #[serde(default)]
+    pub sampler: SamplerKind,
+    #[serde(default)]
+    pub adaptive: Option<AdaptiveSampling>,
+    #[serde(default)]
+    pub filter: Filter,
+    #[serde(default)]
+    pub integrator: IntegratorKind,
+    #[serde(default)]
+    pub aovs: Vec<Aov>,
+    #[serde(default)]
+    pub aov_output: AovOutput,
+    #[serde(default)]
+    pub textures: HashMap<String, TextureConfig>,
+    #[serde(default)]
+    pub materials: HashMap<String, MaterialConfig>,
+    #[serde(default)]
+    pub objects: Vec<ObjectConfig>,
+    // Directory relative paths in the config are resolved from
+    #[serde(skip)]
+    pub base_dir: PathBuf,
+}
+
+impl Config {
+    // Reads the config file, applying the `key.path=value` overrides before deserializing it
+    pub fn load(file_path: &str, overrides: &[(String, String)]) -> Result<Self, String> {
+        let file =
+            std::fs::File::open(file_path).map_err(|err| format!("{}: {}", file_path, err))?;
+        let mut value: Value =
+            serde_json::from_reader(file).map_err(|err| format!("{}: {}", file_path, err))?;
+
+        for (key, raw) in overrides.iter() {
+            apply_override(&mut value, key, raw)
+                .map_err(|err| format!("--set {}: {}", key, err))?;
+        }
+
+        let mut config: Config =
+            serde_json::from_value(value).map_err(|err| format!("{}: {}", file_path, err))?;
+        if matches!(config.integrator, IntegratorKind::BvhCost { .. })
+            && !cfg!(feature = "traversal-stats")
+        {
+            return Err(format!(
+                "{}: the bvh_cost integrator needs the traversal-stats feature",
+                file_path
+            ));
+        }
+        config
+            .filter
+            .validate()
+            .map_err(|err| format!("{}: {}", file_path, err))?;
+        if let Some(dir) = Path::new(file_path).parent() {
+            config.base_dir = dir.to_path_buf();
+        }
+
+        Ok(config)
+    }
+}
+
+// Sets the value at a dotted path like `eye.x` or `objects.0.radius`, missing object keys are
+// created. Values that aren't valid JSON are taken as strings.
+fn apply_override(config: &mut Value, key: &str, raw: &str) -> Result<(), String> {
+    let new_value = serde_json::from_str(raw).unwrap_or_else(|_| Value::String(raw.to_string()));
+
+    let mut current = config;
+    for part in key.split('.') {
+        if part.is_empty() {
+            return Err("Empty key".to_string());
+        }
+
+        current = match current {
+            Value::Object(map) => map.entry(part).or_insert(Value::Null),
+            Value::Array(array) => {
+                let len = array.len();
+                let index: usize = part
+                    .parse()
+                    .map_err(|_| format!("\"{}\" is not an array index", part))?;
+                array
+                    .get_mut(index)
+                    .ok_or_else(|| format!("Index {} out of bounds (length {})", index, len))?
+            }
+            Value::Null => {
+                *current = Value::Object(Default::default());
+                match current {
+                    Value::Object(map) => map.entry(part).or_insert(Value::Null),
+                    _ => unreachable!(),
+                }
+            }
+            _ => return Err(format!("Can't index into a value with \"{}\"", part)),
+        };
+    }
+
+    *current = new_value;
+    Ok(())
+}
+
+fn default_jpeg_quality() -> u8 {
+    90
+}
+
+fn default_roulette_depth() -> u32 {
+    3
+}
+
+fn default_tile_size() -> usize {
+    32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{apply_override, Config};
+    use serde_json::{json, Value};
+
+    fn overridden(mut value: Value, key: &str, raw: &str) -> Result<Value, String> {
+        apply_override(&mut value, key, raw)?;
+        Ok(value)
+    }
+
+    #[test]
+    fn sets_nested_values() {
+        let value = overridden(json!({"eye": {"x": 1, "y": 2}}), "eye.x", "5").unwrap();
+        assert_eq!(value, json!({"eye": {"x": 5, "y": 2}}));
+    }
+
+    #[test]
+    fn creates_missing_keys() {
+        let value = overridden(json!({}), "filter.type", "tent").unwrap();
+        assert_eq!(value, json!({"filter": {"type": "tent"}}));
+    }
+
+    #[test]
+    fn indexes_arrays() {
+        let value = overridden(
+            json!({"objects": [{}, {"radius": 1}]}),
+            "objects.1.radius",
+            "2",
+        );
+        assert_eq!(value.unwrap(), json!({"objects": [{}, {"radius": 2}]}));
+
+        let err = overridden(json!({"objects": [{}]}), "objects.1.radius", "2").unwrap_err();
+        assert_eq!(err, "Index 1 out of bounds (length 1)");
+        let err = overridden(json!({"objects": []}), "objects.first", "2").unwrap_err();
+        assert_eq!(err, "\"first\" is not an array index");
+    }
+
+    #[test]
+    fn rejects_invalid_paths() {
+        let err = overridden(json!({"seed": 1}), "seed.x", "2").unwrap_err();
+        assert_eq!(err, "Can't index into a value with \"x\"");
+        let err = overridden(json!({}), "eye..x", "2").unwrap_err();
+        assert_eq!(err, "Empty key");
+    }
+
+    #[test]
+    fn rejects_unknown_keys() {
+        let scene: Value =
+            serde_json::from_str(include_str!("../../scenes/three_spheres.json")).unwrap();
+        let value = overridden(scene.clone(), "eye.x", "5").unwrap();
+        assert!(serde_json::from_value::<Config>(value).is_ok());
+
+        let value = overridden(scene, "camera.eye.x", "5").unwrap();
+        let err = serde_json::from_value::<Config>(value).err().unwrap();
+        assert!(err.to_string().contains("unknown field `camera`"));
+    }
 }
@@ -0,0 +1,131 @@
+use std::sync::Arc;
+
+use rand::prelude::*;
+use serde::Deserialize;
+
+use crate::hittable::{Hittable, HittableList};
+use crate::material::Material;
+use crate::rng::Pcg32;
+use crate::texture::Texture;
+use crate::vec3::{Color, Vec3};
+
+#[derive(Deserialize, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+pub enum Generator {
+    Random,
+}
+
+impl Generator {
+    // The scene is the same for a given seed
+    pub fn generate(self, seed: u64) -> HittableList {
+        let mut rng = Pcg32::new(seed, 0);
+
+        match self {
+            Self::Random => random_scene(&mut rng),
+        }
+    }
+}
+
+fn random_scene(rng: &mut Pcg32) -> HittableList {
+    let mut world = HittableList::new();
+
+    let ground_material = Material::Lambertian {
+        albedo: Texture::solid(Color::new(0.5, 0.5, 0.5)),
+    };
+
+    let ground = Hittable::Sphere {
+        center: Vec3::new(0.0, -1000.0, 0.0),
+        radius: 1000.0,
+        material: Arc::new(ground_material),
+    };
+    world.add(Arc::new(ground));
+
+    for a in -11..11 {
+        for b in -11..11 {
+            let choose_mat = rng.gen::<f64>();
+            let center = Vec3::new(
+                a as f64 + 0.9 * rng.gen::<f64>(),
+                0.2,
+                b as f64 + 0.9 * rng.gen::<f64>(),
+            );
+
+            if (center - Vec3::new(4.0, 0.2, 0.0)).length() > 0.9 {
+                if choose_mat < 0.8 {
+                    let albedo = random_color(rng, 0.0, 1.0) * random_color(rng, 0.0, 1.0);
+                    let center1 = center + Vec3::new(0.0, rng.gen_range(0.0..=0.5), 0.0);
+
+                    let sphere = Hittable::MovingSphere {
+                        center0: center,
+                        center1,
+                        time0: 0.0,
+                        time1: 1.0,
+                        radius: 0.2,
+                        material: Arc::new(Material::Lambertian {
+                            albedo: Texture::solid(albedo),
+                        }),
+                    };
+
+                    world.add(Arc::new(sphere));
+                } else if choose_mat < 0.95 {
+                    let albedo = random_color(rng, 0.5, 1.0);
+                    let fuzz = rng.gen_range(0.0..=0.5);
+
+                    let sphere = Hittable::Sphere {
+                        center,
+                        radius: 0.2,
+                        material: Arc::new(Material::Metal {
+                            albedo: Texture::solid(albedo),
+                            fuzz,
+                        }),
+                    };
+
+                    world.add(Arc::new(sphere));
+                } else {
+                    let sphere = Hittable::Sphere {
+                        center,
+                        radius: 0.2,
+                        material: Arc::new(Material::Dielectric { ref_idx: 1.5 }),
+                    };
+
+                    world.add(Arc::new(sphere));
+                }
+            }
+        }
+    }
+
+    let glass_sphere = Hittable::Sphere {
+        center: Vec3::new(0.0, 1.0, 0.0),
+        radius: 1.0,
+        material: Arc::new(Material::Dielectric { ref_idx: 1.5 }),
+    };
+    world.add(Arc::new(glass_sphere));
+
+    let mat_sphere = Hittable::Sphere {
+        center: Vec3::new(-4.0, 1.0, 0.0),
+        radius: 1.0,
+        material: Arc::new(Material::Lambertian {
+            albedo: Texture::solid(Color::new(0.4, 0.2, 0.1)),
+        }),
+    };
+    world.add(Arc::new(mat_sphere));
+
+    let metal_sphere = Hittable::Sphere {
+        center: Vec3::new(4.0, 1.0, 0.0),
+        radius: 1.0,
+        material: Arc::new(Material::Metal {
+            albedo: Texture::solid(Color::new(0.7, 0.6, 0.5)),
+            fuzz: 0.0,
+        }),
+    };
+    world.add(Arc::new(metal_sphere));
+
+    world
+}
+
+fn random_color(rng: &mut Pcg32, min: f64, max: f64) -> Color {
+    Color::new(
+        rng.gen_range(min..=max),
+        rng.gen_range(min..=max),
+        rng.gen_range(min..=max),
+    )
+}
@@ -0,0 +1,215 @@
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::Arc;
+
+use serde::Deserialize;
+
+use crate::material::Material;
+use crate::rng::{hash_str, Pcg32};
+use crate::texture::{NoiseKind, Perlin, Texture};
+use crate::vec3::Color;
+
+// A plain color or the name of an entry in the `textures` map
+#[derive(Deserialize, Clone)]
+#[serde(untagged)]
+pub enum TextureRef {
+    Color(Color),
+    Named(String),
+}
+
+impl TextureRef {
+    pub fn resolve(
+        &self,
+        textures: &HashMap<String, Arc<Texture>>,
+    ) -> Result<Arc<Texture>, String> {
+        match self {
+            Self::Color(color) => Ok(Texture::solid(*color)),
+            Self::Named(name) => textures
+                .get(name)
+                .cloned()
+                .ok_or(format!("Unknown texture \"{}\"", name)),
+        }
+    }
+}
+
+#[derive(Deserialize, Clone)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+pub enum TextureConfig {
+    Solid {
+        color: Color,
+    },
+    Checker {
+        odd: TextureRef,
+        even: TextureRef,
+        #[serde(default = "default_checker_scale")]
+        scale: f64,
+    },
+    Image {
+        path: String,
+    },
+    Noise {
+        #[serde(default = "default_noise_color")]
+        color: Color,
+        #[serde(default = "default_noise_scale")]
+        scale: f64,
+        #[serde(default = "default_noise_kind")]
+        kind: NoiseKind,
+        #[serde(default = "default_noise_depth")]
+        depth: usize,
+    },
+    VertexColor,
+}
+
+fn default_checker_scale() -> f64 {
+    1.0
+}
+
+fn default_noise_color() -> Color {
+    Color::new(1.0, 1.0, 1.0)
+}
+
+fn default_noise_scale() -> f64 {
+    1.0
+}
+
+fn default_noise_kind() -> NoiseKind {
+    NoiseKind::Noise
+}
+
+fn default_noise_depth() -> usize {
+    7
+}
+
+impl TextureConfig {
+    fn dependencies(&self) -> Vec<&str> {
+        match self {
+            Self::Checker { odd, even, .. } => [odd, even]
+                .iter()
+                .filter_map(|texture| match texture {
+                    TextureRef::Named(name) => Some(name.as_str()),
+                    TextureRef::Color(_) => None,
+                })
+                .collect(),
+            _ => vec![],
+        }
+    }
+
+    fn build(
+        &self,
+        textures: &HashMap<String, Arc<Texture>>,
+        base_dir: &Path,
+        seed: u64,
+    ) -> Result<Texture, String> {
+        match self {
+            Self::Solid { color } => Ok(Texture::Solid { color: *color }),
+            Self::Checker { odd, even, scale } => Ok(Texture::Checker {
+                odd: odd.resolve(textures)?,
+                even: even.resolve(textures)?,
+                scale: *scale,
+            }),
+            Self::Image { path } => Texture::load_image(&base_dir.join(path)),
+            Self::Noise {
+                color,
+                scale,
+                kind,
+                depth,
+            } => Ok(Texture::Noise {
+                perlin: Perlin::new(&mut Pcg32::new(seed, 0)),
+                color: *color,
+                scale: *scale,
+                kind: *kind,
+                depth: *depth,
+            }),
+            Self::VertexColor => Ok(Texture::VertexColor),
+        }
+    }
+}
+
+// Builds every named texture, textures referenced by others are built first
+// Each texture gets its own seed derived from its name, so the result doesn't depend on the
+// order textures are built in
+pub fn build_textures(
+    configs: &HashMap<String, TextureConfig>,
+    base_dir: &Path,
+    seed: u64,
+) -> Result<HashMap<String, Arc<Texture>>, String> {
+    fn build(
+        name: &str,
+        configs: &HashMap<String, TextureConfig>,
+        base_dir: &Path,
+        seed: u64,
+        textures: &mut HashMap<String, Arc<Texture>>,
+        visiting: &mut Vec<String>,
+    ) -> Result<(), String> {
+        if textures.contains_key(name) {
+            return Ok(());
+        }
+        if visiting.iter().any(|n| n == name) {
+            return Err(format!("Texture \"{}\" references itself", name));
+        }
+
+        let config = configs
+            .get(name)
+            .ok_or(format!("Unknown texture \"{}\"", name))?;
+
+        visiting.push(name.to_string());
+        for dependency in config.dependencies() {
+            build(dependency, configs, base_dir, seed, textures, visiting)?;
+        }
+        visiting.pop();
+
+        let texture = config.build(textures, base_dir, seed ^ hash_str(name))?;
+        textures.insert(name.to_string(), Arc::new(texture));
+        Ok(())
+    }
+
+    let mut textures = HashMap::new();
+    for name in configs.keys() {
+        build(name, configs, base_dir, seed, &mut textures, &mut vec![])?;
+    }
+
+    Ok(textures)
+}
+
+#[derive(Deserialize, Clone)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+pub enum MaterialConfig {
+    Lambertian {
+        albedo: TextureRef,
+    },
+    Metal {
+        albedo: TextureRef,
+        fuzz: f64,
+    },
+    Dielectric {
+        ref_idx: f64,
+    },
+    DiffuseLight {
+        color: Color,
+        #[serde(default = "default_intensity")]
+        intensity: f64,
+    },
+}
+
+fn default_intensity() -> f64 {
+    1.0
+}
+
+impl MaterialConfig {
+    pub fn build(&self, textures: &HashMap<String, Arc<Texture>>) -> Result<Material, String> {
+        match self {
+            Self::Lambertian { albedo } => Ok(Material::Lambertian {
+                albedo: albedo.resolve(textures)?,
+            }),
+            Self::Metal { albedo, fuzz } => Ok(Material::Metal {
+                albedo: albedo.resolve(textures)?,
+                fuzz: *fuzz,
+            }),
+            Self::Dielectric { ref_idx } => Ok(Material::Dielectric { ref_idx: *ref_idx }),
+            Self::DiffuseLight { color, intensity } => Ok(Material::DiffuseLight {
+                color: *color,
+                intensity: *intensity,
+            }),
+        }
+    }
+}
@@ -0,0 +1,288 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use serde::Deserialize;
+
+use super::material_config::{build_textures, MaterialConfig};
+use super::Config;
+use crate::hittable::{Hittable, Mesh};
+use crate::loaders::{load_obj, load_ply};
+use crate::material::Material;
+use crate::texture::Texture;
+use crate::transform::{Matrix4, Transform};
+use crate::vec3::Vec3;
+
+// Named textures, materials and meshes loaded from files, shared by every object using them
+pub struct SceneAssets<'a> {
+    textures: HashMap<String, Arc<Texture>>,
+    materials: HashMap<String, Arc<Material>>,
+    base_dir: &'a Path,
+    meshes: HashMap<PathBuf, Vec<Arc<Mesh>>>,
+}
+
+impl<'a> SceneAssets<'a> {
+    pub fn new(config: &'a Config) -> Result<Self, String> {
+        let textures = build_textures(&config.textures, &config.base_dir, config.seed)?;
+
+        let mut materials = HashMap::new();
+        for (name, material) in config.materials.iter() {
+            let material = material
+                .build(&textures)
+                .map_err(|err| format!("Material \"{}\": {}", name, err))?;
+            materials.insert(name.clone(), Arc::new(material));
+        }
+
+        Ok(Self {
+            textures,
+            materials,
+            base_dir: &config.base_dir,
+            meshes: HashMap::new(),
+        })
+    }
+
+    fn load_obj(&mut self, path: &str) -> Result<Vec<Arc<Mesh>>, String> {
+        let path = self.base_dir.join(path);
+        if let Some(meshes) = self.meshes.get(&path) {
+            return Ok(meshes.clone());
+        }
+
+        let meshes: Vec<_> = load_obj(&path)?.into_iter().map(Arc::new).collect();
+        self.meshes.insert(path, meshes.clone());
+        Ok(meshes)
+    }
+
+    fn load_ply(&mut self, path: &str) -> Result<Vec<Arc<Mesh>>, String> {
+        let path = self.base_dir.join(path);
+        if let Some(meshes) = self.meshes.get(&path) {
+            return Ok(meshes.clone());
+        }
+
+        let meshes = vec![Arc::new(load_ply(&path)?)];
+        self.meshes.insert(path, meshes.clone());
+        Ok(meshes)
+    }
+}
+
+#[derive(Deserialize, Clone)]
+#[serde(untagged)]
+pub enum MaterialRef {
+    Named(String),
+    Inline(MaterialConfig),
+}
+
+impl MaterialRef {
+    pub fn resolve(&self, assets: &SceneAssets) -> Result<Arc<Material>, String> {
+        match self {
+            Self::Named(name) => assets
+                .materials
+                .get(name)
+                .cloned()
+                .ok_or(format!("Unknown material \"{}\"", name)),
+            Self::Inline(material) => Ok(Arc::new(material.build(&assets.textures)?)),
+        }
+    }
+}
+
+// Scale, then rotation in degrees around the x, y and z axes (in that order), then translation.
+// A row major `matrix` can be given instead.
+#[derive(Deserialize, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct TransformConfig {
+    #[serde(default)]
+    pub matrix: Option<[[f64; 4]; 4]>,
+    #[serde(default = "Vec3::zero")]
+    pub translate: Vec3,
+    #[serde(default = "Vec3::zero")]
+    pub rotate: Vec3,
+    #[serde(default = "default_scale")]
+    pub scale: Vec3,
+}
+
+fn default_scale() -> Vec3 {
+    Vec3::new(1.0, 1.0, 1.0)
+}
+
+impl Default for TransformConfig {
+    fn default() -> Self {
+        Self {
+            matrix: None,
+            translate: Vec3::zero(),
+            rotate: Vec3::zero(),
+            scale: default_scale(),
+        }
+    }
+}
+
+impl TransformConfig {
+    pub fn to_transform(&self) -> Result<Transform, String> {
+        if let Some(matrix) = self.matrix {
+            return Transform::new(Matrix4::new(matrix))
+                .ok_or_else(|| "Transform matrix is not invertible".to_string());
+        }
+
+        let scale = Transform::scale(self.scale).ok_or("Transform scale can't be 0")?;
+        Ok(scale
+            .then(&Transform::rotate_x(self.rotate.x))
+            .then(&Transform::rotate_y(self.rotate.y))
+            .then(&Transform::rotate_z(self.rotate.z))
+            .then(&Transform::translate(self.translate)))
+    }
+}
+
+#[derive(Deserialize, Clone)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+pub enum ObjectConfig {
+    Sphere {
+        center: Vec3,
+        radius: f64,
+        material: MaterialRef,
+    },
+    MovingSphere {
+        center0: Vec3,
+        center1: Vec3,
+        time0: f64,
+        time1: f64,
+        radius: f64,
+        material: MaterialRef,
+    },
+    Triangle {
+        v0: Vec3,
+        v1: Vec3,
+        v2: Vec3,
+        material: MaterialRef,
+    },
+    Mesh {
+        positions: Vec<Vec3>,
+        indices: Vec<usize>,
+        #[serde(default)]
+        normals: Vec<Vec3>,
+        #[serde(default)]
+        uvs: Vec<(f64, f64)>,
+        material: MaterialRef,
+    },
+    Obj {
+        path: String,
+        #[serde(default)]
+        material: Option<MaterialRef>,
+        #[serde(default)]
+        transform: TransformConfig,
+    },
+    Ply {
+        path: String,
+        #[serde(default)]
+        material: Option<MaterialRef>,
+        #[serde(default)]
+        transform: TransformConfig,
+    },
+}
+
+impl ObjectConfig {
+    pub fn build(&self, assets: &mut SceneAssets) -> Result<Vec<Hittable>, String> {
+        let object = match self {
+            Self::Sphere {
+                center,
+                radius,
+                material,
+            } => Hittable::Sphere {
+                center: *center,
+                radius: *radius,
+                material: material.resolve(assets)?,
+            },
+            Self::MovingSphere {
+                center0,
+                center1,
+                time0,
+                time1,
+                radius,
+                material,
+            } => Hittable::MovingSphere {
+                center0: *center0,
+                center1: *center1,
+                time0: *time0,
+                time1: *time1,
+                radius: *radius,
+                material: material.resolve(assets)?,
+            },
+            Self::Triangle {
+                v0,
+                v1,
+                v2,
+                material,
+            } => Hittable::Triangle {
+                v0: *v0,
+                v1: *v1,
+                v2: *v2,
+                material: material.resolve(assets)?,
+            },
+            Self::Mesh {
+                positions,
+                indices,
+                normals,
+                uvs,
+                material,
+            } => {
+                let mesh = Mesh::new(
+                    positions.clone(),
+                    normals.clone(),
+                    uvs.clone(),
+                    vec![],
+                    indices.clone(),
+                    material.resolve(assets)?,
+                )?;
+                Hittable::Mesh {
+                    mesh: Arc::new(mesh),
+                }
+            }
+            Self::Obj {
+                path,
+                material,
+                transform,
+            } => {
+                let material = match material {
+                    Some(material) => Some(material.resolve(assets)?),
+                    None => None,
+                };
+                let transform = transform.to_transform()?;
+                let meshes = assets.load_obj(path)?;
+
+                return Ok(meshes
+                    .into_iter()
+                    .map(|mesh| instance(mesh, transform, material.clone()))
+                    .collect());
+            }
+            Self::Ply {
+                path,
+                material,
+                transform,
+            } => {
+                let material = match material {
+                    Some(material) => Some(material.resolve(assets)?),
+                    None => None,
+                };
+                let transform = transform.to_transform()?;
+                let meshes = assets.load_ply(path)?;
+
+                return Ok(meshes
+                    .into_iter()
+                    .map(|mesh| instance(mesh, transform, material.clone()))
+                    .collect());
+            }
+        };
+
+        Ok(vec![object])
+    }
+}
+
+fn instance(mesh: Arc<Mesh>, transform: Transform, material: Option<Arc<Material>>) -> Hittable {
+    let object = Hittable::Mesh { mesh };
+    if transform.is_identity() && material.is_none() {
+        return object;
+    }
+
+    Hittable::Instance {
+        object: Arc::new(object),
+        transform: Box::new(transform),
+        material,
+    }
+}
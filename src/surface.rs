@@ -1,34 +1,131 @@
-use crate::rgbcolor::RGBColor;
+use crate::adaptive::heatmap_color;
+use crate::aov::{aov_files, Aov, AovOutput};
+use crate::filter::Filter;
+use crate::output::{self, OutputSettings};
+use crate::vec3::Color;
 
+// Linear radiance accumulated per pixel, quantized only when the image is saved. Pixels store
+// the sum of the filter weighted samples around them and the sum of the weights. Filtered AOV
+// layers are accumulated the same way with the same weights, the others keep the first sample of
+// each pixel.
 pub struct Surface {
     x_offset: usize,
     y_offset: usize,
     width: usize,
     height: usize,
-    buffer: Vec<RGBColor>,
+    buffer: Vec<Color>,
+    layers: Vec<Vec<Color>>,
+    filtered: Vec<bool>,
+    weights: Vec<f64>,
+    sample_counts: Vec<u32>,
 }
 
 impl Surface {
-    pub fn new(x_offset: usize, y_offset: usize, width: usize, height: usize) -> Self {
-        let mut buffer: Vec<RGBColor> = vec![];
-        buffer.resize(width * height, RGBColor { r: 0, g: 0, b: 0 });
+    pub fn new(
+        x_offset: usize,
+        y_offset: usize,
+        width: usize,
+        height: usize,
+        aovs: &[Aov],
+    ) -> Self {
         Self {
             x_offset,
             y_offset,
             width,
             height,
-            buffer,
+            buffer: vec![Color::zero(); width * height],
+            layers: vec![vec![Color::zero(); width * height]; aovs.len()],
+            filtered: aovs.iter().map(|aov| aov.is_filtered()).collect(),
+            weights: vec![0.0; width * height],
+            sample_counts: vec![0; width * height],
         }
     }
 
-    pub fn get_color(&self, x: usize, y: usize) -> RGBColor {
-        self.buffer[x + y * self.width].clone()
+    // Adds a sample of pixel (x, y) taken at `position`, both in image coordinates, to every
+    // pixel of the surface in the filter radius. Pixel centers are at half integer coordinates.
+    // `layers` has a value for every AOV layer.
+    pub fn add_sample(
+        &mut self,
+        x: usize,
+        y: usize,
+        position: (f64, f64),
+        color: Color,
+        layers: &[Color],
+        filter: &Filter,
+    ) {
+        let radius = filter.radius();
+        let (x_start, x_end) = self.pixel_range(position.0, radius, self.x_offset, self.width);
+        let (y_start, y_end) = self.pixel_range(position.1, radius, self.y_offset, self.height);
+
+        for py in y_start..y_end {
+            for px in x_start..x_end {
+                let weight =
+                    filter.evaluate(px as f64 + 0.5 - position.0, py as f64 + 0.5 - position.1);
+                if weight == 0.0 {
+                    continue;
+                }
+
+                let i = (px - self.x_offset) + (py - self.y_offset) * self.width;
+                self.buffer[i] += weight * color;
+                for ((layer, &value), &filtered) in self
+                    .layers
+                    .iter_mut()
+                    .zip(layers.iter())
+                    .zip(self.filtered.iter())
+                {
+                    if filtered {
+                        layer[i] += weight * value;
+                    }
+                }
+                self.weights[i] += weight;
+            }
+        }
+
+        if x >= self.x_offset && y >= self.y_offset {
+            let (x, y) = (x - self.x_offset, y - self.y_offset);
+            if x < self.width && y < self.height {
+                let i = x + y * self.width;
+                if self.sample_counts[i] == 0 {
+                    for ((layer, &value), &filtered) in self
+                        .layers
+                        .iter_mut()
+                        .zip(layers.iter())
+                        .zip(self.filtered.iter())
+                    {
+                        if !filtered {
+                            layer[i] = value;
+                        }
+                    }
+                }
+                self.sample_counts[i] += 1;
+            }
+        }
+    }
+
+    // Pixels whose center is within `radius` of `position` along one axis, clipped to the surface.
+    // The range is half open on the low side so samples on a pixel border count only once.
+    fn pixel_range(&self, position: f64, radius: f64, offset: usize, len: usize) -> (usize, usize) {
+        let start = (position - 0.5 - radius).floor() + 1.0;
+        let end = (position - 0.5 + radius).floor() + 1.0;
+        let start = start.max(offset as f64) as usize;
+        let end = end.min((offset + len) as f64).max(0.0) as usize;
+        (start, std::cmp::max(start, end))
     }
 
-    pub fn set_color(&mut self, x: usize, y: usize, color: RGBColor) {
-        self.buffer[x + y * self.width] = color;
+    // Filtered radiance of the pixel
+    pub fn get_color(&self, x: usize, y: usize) -> Color {
+        self.resolve(&self.buffer, x + y * self.width)
+    }
+
+    fn resolve(&self, buffer: &[Color], i: usize) -> Color {
+        if self.weights[i] == 0.0 {
+            Color::zero()
+        } else {
+            buffer[i] / self.weights[i]
+        }
     }
 
+    // Accumulates the samples of `other` into this surface
     pub fn merge(&mut self, other: &Surface) {
         let x_len = std::cmp::min(other.width, self.width - other.x_offset);
         let y_len = std::cmp::min(other.height, self.height - other.y_offset);
@@ -37,21 +134,93 @@ impl Surface {
             let dst_y = other.y_offset + src_y;
             for src_x in 0..x_len {
                 let dst_x = other.x_offset + src_x;
-                self.buffer[dst_x + dst_y * self.width] =
-                    other.buffer[src_x + src_y * other.width].clone();
+                let src = src_x + src_y * other.width;
+                let dst = dst_x + dst_y * self.width;
+                self.buffer[dst] += other.buffer[src];
+                for ((layer, other_layer), &filtered) in self
+                    .layers
+                    .iter_mut()
+                    .zip(other.layers.iter())
+                    .zip(self.filtered.iter())
+                {
+                    if filtered {
+                        layer[dst] += other_layer[src];
+                    } else if other.sample_counts[src] > 0 && self.sample_counts[dst] == 0 {
+                        layer[dst] = other_layer[src];
+                    }
+                }
+                self.weights[dst] += other.weights[src];
+                self.sample_counts[dst] += other.sample_counts[src];
             }
         }
     }
 
-    pub fn save(&self, path: &str) -> image::ImageResult<()> {
-        let mut img = image::ImageBuffer::new(self.width as u32, self.height as u32);
-        for y in 0..self.height {
-            for x in 0..self.width {
-                let pixel = self.get_color(x, y);
-                *img.get_pixel_mut(x as u32, y as u32) = image::Rgb([pixel.r, pixel.g, pixel.b]);
-            }
+    pub fn total_samples(&self) -> u64 {
+        self.sample_counts.iter().map(|&count| count as u64).sum()
+    }
+
+    // Average radiance of every pixel, row by row from the top
+    pub fn pixels(&self) -> Vec<Color> {
+        (0..self.height)
+            .flat_map(|y| (0..self.width).map(move |x| (x, y)))
+            .map(|(x, y)| self.get_color(x, y))
+            .collect()
+    }
+
+    // Values of an AOV layer, row by row from the top
+    pub fn layer_pixels(&self, layer: usize) -> Vec<Color> {
+        if !self.filtered[layer] {
+            return self.layers[layer].clone();
         }
+        (0..self.width * self.height)
+            .map(|i| self.resolve(&self.layers[layer], i))
+            .collect()
+    }
 
-        img.save(path)
+    // Heatmap of the number of samples taken for each pixel
+    pub fn save_sample_counts(
+        &self,
+        settings: &OutputSettings,
+        max_samples: u32,
+    ) -> Result<(), String> {
+        let pixels: Vec<Color> = self
+            .sample_counts
+            .iter()
+            .map(|&count| heatmap_color(count, max_samples))
+            .collect();
+        output::write_image(settings, self.width, self.height, &pixels)
+    }
+
+    // Saves the image and the AOV layers, `aovs` names the layers in order
+    pub fn save(
+        &self,
+        settings: &OutputSettings,
+        aovs: &[Aov],
+        aov_output: AovOutput,
+    ) -> Result<(), String> {
+        let files = aov_files(settings, aovs, aov_output);
+        if files.is_empty() && !aovs.is_empty() {
+            let layers: Vec<Vec<Color>> = (0..aovs.len()).map(|i| self.layer_pixels(i)).collect();
+            let pixels = self.pixels();
+            let named: Vec<(&str, &[Color])> = std::iter::once(("", pixels.as_slice()))
+                .chain(
+                    aovs.iter()
+                        .zip(layers.iter())
+                        .map(|(aov, layer)| (aov.name(), layer.as_slice())),
+                )
+                .collect();
+            return output::write_layers(settings, self.width, self.height, &named);
+        }
+
+        output::write_image(settings, self.width, self.height, &self.pixels())?;
+        for (i, (_, file_settings)) in files.iter().enumerate() {
+            output::write_image(
+                file_settings,
+                self.width,
+                self.height,
+                &self.layer_pixels(i),
+            )?;
+        }
+        Ok(())
     }
 }
@@ -0,0 +1,116 @@
+pub mod perlin;
+
+pub use self::perlin::Perlin;
+
+use std::path::Path;
+use std::sync::Arc;
+
+use serde::Deserialize;
+
+use crate::hittable::Intersection;
+use crate::math;
+use crate::vec3::Color;
+
+#[derive(Deserialize, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+pub enum NoiseKind {
+    Noise,
+    Turbulence,
+    Marble,
+}
+
+pub enum Texture {
+    Solid {
+        color: Color,
+    },
+    // 3D checker pattern, `scale` is the number of squares per unit
+    Checker {
+        odd: Arc<Texture>,
+        even: Arc<Texture>,
+        scale: f64,
+    },
+    Image {
+        image: image::RgbImage,
+    },
+    Noise {
+        perlin: Perlin,
+        color: Color,
+        scale: f64,
+        kind: NoiseKind,
+        depth: usize,
+    },
+    // Interpolated mesh vertex colors, white on surfaces without them
+    VertexColor,
+}
+
+impl Texture {
+    pub fn solid(color: Color) -> Arc<Self> {
+        Arc::new(Self::Solid { color })
+    }
+
+    pub fn load_image(path: &Path) -> Result<Self, String> {
+        let image = image::open(path)
+            .map_err(|err| format!("Could not load \"{}\": {}", path.display(), err))?;
+        Ok(Self::Image {
+            image: image.to_rgb8(),
+        })
+    }
+
+    pub fn value(&self, intersection: &Intersection) -> Color {
+        let (u, v, point) = (intersection.u, intersection.v, intersection.point);
+        match self {
+            Self::Solid { color } => *color,
+            Self::Checker { odd, even, scale } => {
+                let sines = (scale * std::f64::consts::PI * point.x).sin()
+                    * (scale * std::f64::consts::PI * point.y).sin()
+                    * (scale * std::f64::consts::PI * point.z).sin();
+                if sines < 0.0 {
+                    odd.value(intersection)
+                } else {
+                    even.value(intersection)
+                }
+            }
+            Self::Image { image } => image_value(image, u, v),
+            Self::Noise {
+                perlin,
+                color,
+                scale,
+                kind,
+                depth,
+            } => {
+                let p = *scale * point;
+                let value = match kind {
+                    NoiseKind::Noise => 0.5 * (1.0 + perlin.noise(p)),
+                    NoiseKind::Turbulence => perlin.turbulence(p, *depth),
+                    NoiseKind::Marble => {
+                        0.5 * (1.0 + (p.z + 10.0 * perlin.turbulence(p, *depth)).sin())
+                    }
+                };
+                value * *color
+            }
+            Self::VertexColor => intersection
+                .vertex_color
+                .unwrap_or_else(|| Color::new(1.0, 1.0, 1.0)),
+        }
+    }
+}
+
+// Nearest texel lookup, uvs repeat outside of [0, 1] and v goes up the image
+fn image_value(image: &image::RgbImage, u: f64, v: f64) -> Color {
+    let (width, height) = image.dimensions();
+    if width == 0 || height == 0 {
+        return Color::new(0.0, 1.0, 1.0);
+    }
+
+    let u = u - u.floor();
+    let v = 1.0 - (v - v.floor());
+    let x = ((u * width as f64) as u32).min(width - 1);
+    let y = ((v * height as f64) as u32).min(height - 1);
+
+    let pixel = image.get_pixel(x, y);
+    Color::new(
+        math::srgb_to_linear(pixel[0] as f64 / 255.0),
+        math::srgb_to_linear(pixel[1] as f64 / 255.0),
+        math::srgb_to_linear(pixel[2] as f64 / 255.0),
+    )
+}
@@ -0,0 +1,95 @@
+use rand::prelude::*;
+
+use crate::rng::Pcg32;
+use crate::vec3::Vec3;
+
+const POINT_COUNT: usize = 256;
+
+pub struct Perlin {
+    random_vectors: Vec<Vec3>,
+    perm_x: Vec<usize>,
+    perm_y: Vec<usize>,
+    perm_z: Vec<usize>,
+}
+
+impl Perlin {
+    pub fn new(rng: &mut Pcg32) -> Self {
+        let random_vectors = (0..POINT_COUNT)
+            .map(|_| Vec3::random_vec3_range(rng, -1.0, 1.0).normalize())
+            .collect();
+
+        Self {
+            random_vectors,
+            perm_x: generate_perm(rng),
+            perm_y: generate_perm(rng),
+            perm_z: generate_perm(rng),
+        }
+    }
+
+    pub fn noise(&self, point: Vec3) -> f64 {
+        let u = point.x - point.x.floor();
+        let v = point.y - point.y.floor();
+        let w = point.z - point.z.floor();
+
+        let i = point.x.floor() as i64;
+        let j = point.y.floor() as i64;
+        let k = point.z.floor() as i64;
+
+        let mut c = [[[Vec3::zero(); 2]; 2]; 2];
+        for (di, plane) in c.iter_mut().enumerate() {
+            for (dj, row) in plane.iter_mut().enumerate() {
+                for (dk, value) in row.iter_mut().enumerate() {
+                    let index = self.perm_x[((i + di as i64) & 255) as usize]
+                        ^ self.perm_y[((j + dj as i64) & 255) as usize]
+                        ^ self.perm_z[((k + dk as i64) & 255) as usize];
+                    *value = self.random_vectors[index];
+                }
+            }
+        }
+
+        perlin_interpolation(&c, u, v, w)
+    }
+
+    pub fn turbulence(&self, point: Vec3, depth: usize) -> f64 {
+        let mut accum = 0.0;
+        let mut p = point;
+        let mut weight = 1.0;
+
+        for _ in 0..depth {
+            accum += weight * self.noise(p);
+            weight *= 0.5;
+            p *= 2.0;
+        }
+
+        accum.abs()
+    }
+}
+
+fn generate_perm<R: Rng>(rng: &mut R) -> Vec<usize> {
+    let mut perm: Vec<usize> = (0..POINT_COUNT).collect();
+    perm.shuffle(rng);
+    perm
+}
+
+fn perlin_interpolation(c: &[[[Vec3; 2]; 2]; 2], u: f64, v: f64, w: f64) -> f64 {
+    // Hermite smoothing
+    let uu = u * u * (3.0 - 2.0 * u);
+    let vv = v * v * (3.0 - 2.0 * v);
+    let ww = w * w * (3.0 - 2.0 * w);
+
+    let mut accum = 0.0;
+    for (i, plane) in c.iter().enumerate() {
+        for (j, row) in plane.iter().enumerate() {
+            for (k, value) in row.iter().enumerate() {
+                let (fi, fj, fk) = (i as f64, j as f64, k as f64);
+                let weight = Vec3::new(u - fi, v - fj, w - fk);
+                accum += (fi * uu + (1.0 - fi) * (1.0 - uu))
+                    * (fj * vv + (1.0 - fj) * (1.0 - vv))
+                    * (fk * ww + (1.0 - fk) * (1.0 - ww))
+                    * value.dot(weight);
+            }
+        }
+    }
+
+    accum
+}
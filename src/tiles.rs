@@ -0,0 +1,146 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use serde::Deserialize;
+
+// Order in which the tiles are handed out to the render threads
+#[derive(Deserialize, Clone, Copy, Debug, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum TileOrder {
+    // Row by row from the top left
+    #[default]
+    Scanline,
+    // Outwards from the center of the image
+    Spiral,
+    // Along a Hilbert curve over the next power of two grid, tiles outside the image are skipped
+    // so consecutive tiles are usually, but not always, neighbours
+    Hilbert,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct Tile {
+    // Position of the tile in the queue
+    pub index: usize,
+    pub x: usize,
+    pub y: usize,
+    pub width: usize,
+    pub height: usize,
+}
+
+// Tiles shared by every render thread, each one takes the next tile when it is done with its
+// current one so threads working on cheap parts of the image don't sit idle
+pub struct TileQueue {
+    tiles: Vec<Tile>,
+    next: AtomicUsize,
+}
+
+impl TileQueue {
+    pub fn new(img_width: usize, img_height: usize, tile_size: usize, order: TileOrder) -> Self {
+        let tile_size = std::cmp::max(tile_size, 1);
+        let x_count = img_width.div_ceil(tile_size);
+        let y_count = img_height.div_ceil(tile_size);
+
+        let coords = match order {
+            TileOrder::Scanline => scanline_order(x_count, y_count),
+            TileOrder::Spiral => spiral_order(x_count, y_count),
+            TileOrder::Hilbert => hilbert_order(x_count, y_count),
+        };
+
+        // Tiles on the right and bottom edges are cut to the image size
+        let tiles = coords
+            .into_iter()
+            .enumerate()
+            .map(|(index, (tx, ty))| {
+                let x = tx * tile_size;
+                let y = ty * tile_size;
+                Tile {
+                    index,
+                    x,
+                    y,
+                    width: std::cmp::min(tile_size, img_width - x),
+                    height: std::cmp::min(tile_size, img_height - y),
+                }
+            })
+            .collect();
+
+        Self {
+            tiles,
+            next: AtomicUsize::new(0),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.tiles.len()
+    }
+
+    pub fn next(&self) -> Option<Tile> {
+        let index = self.next.fetch_add(1, Ordering::Relaxed);
+        self.tiles.get(index).copied()
+    }
+}
+
+fn scanline_order(x_count: usize, y_count: usize) -> Vec<(usize, usize)> {
+    (0..y_count)
+        .flat_map(|y| (0..x_count).map(move |x| (x, y)))
+        .collect()
+}
+
+// Square spiral starting from the center tile, steps falling outside the image are skipped
+fn spiral_order(x_count: usize, y_count: usize) -> Vec<(usize, usize)> {
+    let total = x_count * y_count;
+    let mut coords = Vec::with_capacity(total);
+
+    let mut x = ((x_count as isize) - 1) / 2;
+    let mut y = ((y_count as isize) - 1) / 2;
+    let directions = [(1, 0), (0, 1), (-1, 0), (0, -1)];
+    let mut direction = 0;
+    let mut leg_length = 1;
+
+    while coords.len() < total {
+        // Each leg length is walked twice before growing
+        for _ in 0..2 {
+            let (dx, dy) = directions[direction];
+            for _ in 0..leg_length {
+                if x >= 0 && y >= 0 && (x as usize) < x_count && (y as usize) < y_count {
+                    coords.push((x as usize, y as usize));
+                }
+                x += dx;
+                y += dy;
+            }
+            direction = (direction + 1) % 4;
+        }
+        leg_length += 1;
+    }
+
+    coords
+}
+
+fn hilbert_order(x_count: usize, y_count: usize) -> Vec<(usize, usize)> {
+    let size = std::cmp::max(x_count, y_count).next_power_of_two();
+    let mut coords = scanline_order(x_count, y_count);
+    coords.sort_by_key(|&(x, y)| hilbert_index(size, x, y));
+    coords
+}
+
+// Distance along the Hilbert curve filling a `size` x `size` grid, `size` being a power of two
+fn hilbert_index(size: usize, x: usize, y: usize) -> usize {
+    let mut x = x;
+    let mut y = y;
+    let mut index = 0;
+    let mut s = size / 2;
+    while s > 0 {
+        let rx = (x & s > 0) as usize;
+        let ry = (y & s > 0) as usize;
+        index += s * s * ((3 * rx) ^ ry);
+
+        // Rotate the quadrant so the sub curve has the right orientation
+        if ry == 0 {
+            if rx == 1 {
+                x = size - 1 - x;
+                y = size - 1 - y;
+            }
+            std::mem::swap(&mut x, &mut y);
+        }
+        s /= 2;
+    }
+    index
+}
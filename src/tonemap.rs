@@ -0,0 +1,103 @@
+use serde::Deserialize;
+
+use crate::math::{clamp, linear_to_srgb};
+use crate::vec3::{luminance, Color};
+
+// Operators compressing scene radiance into the displayable [0, 1] range
+#[derive(Deserialize, Clone, Copy, Debug, Default)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+pub enum ToneMap {
+    #[default]
+    Clamp,
+    Reinhard,
+    // Reinhard with the luminance mapped to 1 given as `white`
+    ExtendedReinhard {
+        #[serde(default = "default_reinhard_white")]
+        white: f64,
+    },
+    // Narkowicz's fit of the ACES filmic curve
+    Aces,
+    // Uncharted 2 filmic curve by John Hable
+    Hable {
+        #[serde(default = "default_hable_white")]
+        white: f64,
+    },
+}
+
+fn default_reinhard_white() -> f64 {
+    4.0
+}
+
+fn default_hable_white() -> f64 {
+    11.2
+}
+
+impl ToneMap {
+    pub fn apply(&self, color: Color) -> Color {
+        match *self {
+            Self::Clamp => color,
+            Self::Reinhard => scale_luminance(color, |l| l / (1.0 + l)),
+            Self::ExtendedReinhard { white } => {
+                scale_luminance(color, |l| l * (1.0 + l / (white * white)) / (1.0 + l))
+            }
+            Self::Aces => map_channels(color, aces),
+            Self::Hable { white } => {
+                let white_scale = 1.0 / hable(white);
+                map_channels(color, |c| hable(2.0 * c) * white_scale)
+            }
+        }
+    }
+}
+
+// Tone mapping of the linear radiance to sRGB encoded display values
+#[derive(Clone, Copy, Debug, Default)]
+pub struct ToneMapping {
+    pub tone_map: ToneMap,
+    // Exposure adjustment in stops
+    pub exposure: f64,
+}
+
+impl ToneMapping {
+    pub fn new(tone_map: ToneMap, exposure: f64) -> Self {
+        Self { tone_map, exposure }
+    }
+
+    pub fn apply(&self, color: Color) -> Color {
+        let exposed = color * 2f64.powf(self.exposure);
+        map_channels(self.tone_map.apply(exposed), |c| {
+            linear_to_srgb(clamp(c, 0.0, 1.0))
+        })
+    }
+}
+
+// Maps the luminance and keeps the color ratios, so saturated highlights don't shift hue
+fn scale_luminance<F: Fn(f64) -> f64>(color: Color, map: F) -> Color {
+    let l = luminance(color);
+    if l <= 0.0 {
+        return Color::zero();
+    }
+    color * (map(l) / l)
+}
+
+fn map_channels<F: Fn(f64) -> f64>(color: Color, map: F) -> Color {
+    Color::new(map(color.x), map(color.y), map(color.z))
+}
+
+fn aces(x: f64) -> f64 {
+    let a = 2.51;
+    let b = 0.03;
+    let c = 2.43;
+    let d = 0.59;
+    let e = 0.14;
+    (x * (a * x + b)) / (x * (c * x + d) + e)
+}
+
+fn hable(x: f64) -> f64 {
+    let a = 0.15;
+    let b = 0.50;
+    let c = 0.10;
+    let d = 0.20;
+    let e = 0.02;
+    let f = 0.30;
+    ((x * (a * x + c * b) + d * e) / (x * (a * x + b) + d * f)) - e / f
+}
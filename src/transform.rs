@@ -0,0 +1,280 @@
+use crate::axis_aligned_bb::AxisAlignedBB;
+use crate::math;
+use crate::ray::Ray;
+use crate::vec3::Vec3;
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Matrix4 {
+    pub m: [[f64; 4]; 4],
+}
+
+impl Matrix4 {
+    pub const fn new(m: [[f64; 4]; 4]) -> Self {
+        Self { m }
+    }
+
+    pub const fn identity() -> Self {
+        Self::new([
+            [1.0, 0.0, 0.0, 0.0],
+            [0.0, 1.0, 0.0, 0.0],
+            [0.0, 0.0, 1.0, 0.0],
+            [0.0, 0.0, 0.0, 1.0],
+        ])
+    }
+
+    pub fn transpose(&self) -> Self {
+        let mut m = [[0.0; 4]; 4];
+        for (i, row) in m.iter_mut().enumerate() {
+            for (j, value) in row.iter_mut().enumerate() {
+                *value = self.m[j][i];
+            }
+        }
+
+        Self::new(m)
+    }
+
+    // Gauss-Jordan elimination with partial pivoting, None if the matrix is singular
+    pub fn inverse(&self) -> Option<Self> {
+        let mut a = self.m;
+        let mut inv = Self::identity().m;
+
+        for col in 0..4 {
+            let pivot = (col..4)
+                .max_by(|&i, &j| {
+                    a[i][col]
+                        .abs()
+                        .partial_cmp(&a[j][col].abs())
+                        .unwrap_or(std::cmp::Ordering::Equal)
+                })
+                .unwrap_or(col);
+
+            if a[pivot][col].abs() < 1e-12 {
+                return None;
+            }
+
+            a.swap(col, pivot);
+            inv.swap(col, pivot);
+
+            let scale = 1.0 / a[col][col];
+            for j in 0..4 {
+                a[col][j] *= scale;
+                inv[col][j] *= scale;
+            }
+
+            for row in 0..4 {
+                if row != col {
+                    let factor = a[row][col];
+                    for j in 0..4 {
+                        a[row][j] -= factor * a[col][j];
+                        inv[row][j] -= factor * inv[col][j];
+                    }
+                }
+            }
+        }
+
+        Some(Self::new(inv))
+    }
+}
+
+impl std::ops::Mul for Matrix4 {
+    type Output = Self;
+    fn mul(self, other: Self) -> Self {
+        let mut m = [[0.0; 4]; 4];
+        for (i, row) in m.iter_mut().enumerate() {
+            for (j, value) in row.iter_mut().enumerate() {
+                *value = (0..4).map(|k| self.m[i][k] * other.m[k][j]).sum();
+            }
+        }
+
+        Self::new(m)
+    }
+}
+
+// Affine transform stored with its inverse so rays can be brought into object space, and
+// with the inverse transpose that transforms normals.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Transform {
+    matrix: Matrix4,
+    inverse: Matrix4,
+    normal_matrix: Matrix4,
+}
+
+impl Transform {
+    pub fn new(matrix: Matrix4) -> Option<Self> {
+        let inverse = matrix.inverse()?;
+        Some(Self::with_inverse(matrix, inverse))
+    }
+
+    fn with_inverse(matrix: Matrix4, inverse: Matrix4) -> Self {
+        Self {
+            matrix,
+            inverse,
+            normal_matrix: inverse.transpose(),
+        }
+    }
+
+    pub const fn identity() -> Self {
+        Self {
+            matrix: Matrix4::identity(),
+            inverse: Matrix4::identity(),
+            normal_matrix: Matrix4::identity(),
+        }
+    }
+
+    pub fn translate(offset: Vec3) -> Self {
+        let matrix = Matrix4::new([
+            [1.0, 0.0, 0.0, offset.x],
+            [0.0, 1.0, 0.0, offset.y],
+            [0.0, 0.0, 1.0, offset.z],
+            [0.0, 0.0, 0.0, 1.0],
+        ]);
+        let inverse = Matrix4::new([
+            [1.0, 0.0, 0.0, -offset.x],
+            [0.0, 1.0, 0.0, -offset.y],
+            [0.0, 0.0, 1.0, -offset.z],
+            [0.0, 0.0, 0.0, 1.0],
+        ]);
+
+        Self::with_inverse(matrix, inverse)
+    }
+
+    pub fn scale(factors: Vec3) -> Option<Self> {
+        if factors.x == 0.0 || factors.y == 0.0 || factors.z == 0.0 {
+            return None;
+        }
+
+        let matrix = Matrix4::new([
+            [factors.x, 0.0, 0.0, 0.0],
+            [0.0, factors.y, 0.0, 0.0],
+            [0.0, 0.0, factors.z, 0.0],
+            [0.0, 0.0, 0.0, 1.0],
+        ]);
+        let inverse = Matrix4::new([
+            [1.0 / factors.x, 0.0, 0.0, 0.0],
+            [0.0, 1.0 / factors.y, 0.0, 0.0],
+            [0.0, 0.0, 1.0 / factors.z, 0.0],
+            [0.0, 0.0, 0.0, 1.0],
+        ]);
+
+        Some(Self::with_inverse(matrix, inverse))
+    }
+
+    // Rotation of `degrees` around `axis` following the right hand rule
+    pub fn rotate(axis: Vec3, degrees: f64) -> Self {
+        let a = axis.normalize();
+        let (sin, cos) = math::degrees_to_radians(degrees).sin_cos();
+
+        let matrix = Matrix4::new([
+            [
+                a.x * a.x + (1.0 - a.x * a.x) * cos,
+                a.x * a.y * (1.0 - cos) - a.z * sin,
+                a.x * a.z * (1.0 - cos) + a.y * sin,
+                0.0,
+            ],
+            [
+                a.x * a.y * (1.0 - cos) + a.z * sin,
+                a.y * a.y + (1.0 - a.y * a.y) * cos,
+                a.y * a.z * (1.0 - cos) - a.x * sin,
+                0.0,
+            ],
+            [
+                a.x * a.z * (1.0 - cos) - a.y * sin,
+                a.y * a.z * (1.0 - cos) + a.x * sin,
+                a.z * a.z + (1.0 - a.z * a.z) * cos,
+                0.0,
+            ],
+            [0.0, 0.0, 0.0, 1.0],
+        ]);
+
+        Self::with_inverse(matrix, matrix.transpose())
+    }
+
+    pub fn rotate_x(degrees: f64) -> Self {
+        Self::rotate(Vec3::new(1.0, 0.0, 0.0), degrees)
+    }
+
+    pub fn rotate_y(degrees: f64) -> Self {
+        Self::rotate(Vec3::new(0.0, 1.0, 0.0), degrees)
+    }
+
+    pub fn rotate_z(degrees: f64) -> Self {
+        Self::rotate(Vec3::new(0.0, 0.0, 1.0), degrees)
+    }
+
+    // Transform applying `self` first, then `other`
+    pub fn then(&self, other: &Self) -> Self {
+        Self::with_inverse(other.matrix * self.matrix, self.inverse * other.inverse)
+    }
+
+    pub fn is_identity(&self) -> bool {
+        self.matrix == Matrix4::identity()
+    }
+
+    pub fn apply_point(&self, p: Vec3) -> Vec3 {
+        transform_point(&self.matrix, p)
+    }
+
+    pub fn apply_vector(&self, v: Vec3) -> Vec3 {
+        transform_vector(&self.matrix, v)
+    }
+
+    // Normals are transformed by the inverse transpose, the result is not normalized
+    pub fn apply_normal(&self, n: Vec3) -> Vec3 {
+        transform_vector(&self.normal_matrix, n)
+    }
+
+    // The direction is not normalized so hit distances stay the same in both spaces
+    pub fn apply_inverse_ray(&self, ray: Ray) -> Ray {
+        Ray::new(
+            transform_point(&self.inverse, ray.origin),
+            transform_vector(&self.inverse, ray.dir),
+            ray.time,
+        )
+    }
+
+    pub fn apply_bounding_box(&self, bb: &AxisAlignedBB) -> AxisAlignedBB {
+        let mut out = AxisAlignedBB::empty();
+        for i in 0..8 {
+            let corner = Vec3::new(
+                if i & 1 == 0 { bb.min().x } else { bb.max().x },
+                if i & 2 == 0 { bb.min().y } else { bb.max().y },
+                if i & 4 == 0 { bb.min().z } else { bb.max().z },
+            );
+            out = AxisAlignedBB::surrounding_box(
+                &out,
+                &AxisAlignedBB::from_point(self.apply_point(corner)),
+            );
+        }
+
+        out
+    }
+}
+
+impl Default for Transform {
+    fn default() -> Self {
+        Self::identity()
+    }
+}
+
+fn transform_point(m: &Matrix4, p: Vec3) -> Vec3 {
+    let m = &m.m;
+    let x = m[0][0] * p.x + m[0][1] * p.y + m[0][2] * p.z + m[0][3];
+    let y = m[1][0] * p.x + m[1][1] * p.y + m[1][2] * p.z + m[1][3];
+    let z = m[2][0] * p.x + m[2][1] * p.y + m[2][2] * p.z + m[2][3];
+    let w = m[3][0] * p.x + m[3][1] * p.y + m[3][2] * p.z + m[3][3];
+
+    if w == 1.0 {
+        Vec3::new(x, y, z)
+    } else {
+        Vec3::new(x, y, z) / w
+    }
+}
+
+fn transform_vector(m: &Matrix4, v: Vec3) -> Vec3 {
+    let m = &m.m;
+    Vec3::new(
+        m[0][0] * v.x + m[0][1] * v.y + m[0][2] * v.z,
+        m[1][0] * v.x + m[1][1] * v.y + m[1][2] * v.z,
+        m[2][0] * v.x + m[2][1] * v.y + m[2][2] * v.z,
+    )
+}
@@ -0,0 +1,28 @@
+// Number of bounding box and primitive intersection tests done by the current thread, used to
+// show the cost of tracing each pixel. Counting is only compiled in with the `traversal-stats`
+// feature so normal renders don't pay for it.
+#[cfg(feature = "traversal-stats")]
+thread_local! {
+    static TESTS: std::cell::Cell<u64> = const { std::cell::Cell::new(0) };
+}
+
+#[cfg(feature = "traversal-stats")]
+#[inline]
+pub fn count_test() {
+    TESTS.with(|tests| tests.set(tests.get() + 1));
+}
+
+#[cfg(not(feature = "traversal-stats"))]
+#[inline(always)]
+pub fn count_test() {}
+
+// Tests counted since the last call
+#[cfg(feature = "traversal-stats")]
+pub fn take_test_count() -> u64 {
+    TESTS.with(|tests| tests.replace(0))
+}
+
+#[cfg(not(feature = "traversal-stats"))]
+pub fn take_test_count() -> u64 {
+    0
+}
@@ -6,6 +6,7 @@ use serde::Deserialize;
 pub type Color = Vec3;
 
 #[derive(Debug, Copy, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
 pub struct Vec3 {
     pub x: f64,
     pub y: f64,
@@ -25,8 +26,7 @@ impl Vec3 {
         }
     }
 
-    pub fn random_color_range(min: f64, max: f64) -> Color {
-        let mut rng = rand::thread_rng();
+    pub fn random_color_range<R: Rng + ?Sized>(rng: &mut R, min: f64, max: f64) -> Color {
         Color {
             x: rng.gen_range(min..=max),
             y: rng.gen_range(min..=max),
@@ -34,12 +34,11 @@ impl Vec3 {
         }
     }
 
-    pub fn random_color() -> Color {
-        Vec3::random_color_range(0.0, 1.0)
+    pub fn random_color<R: Rng + ?Sized>(rng: &mut R) -> Color {
+        Vec3::random_color_range(rng, 0.0, 1.0)
     }
 
-    pub fn random_vec3_range(min: f64, max: f64) -> Vec3 {
-        let mut rng = rand::thread_rng();
+    pub fn random_vec3_range<R: Rng + ?Sized>(rng: &mut R, min: f64, max: f64) -> Vec3 {
         Vec3 {
             x: rng.gen_range(min..=max),
             y: rng.gen_range(min..=max),
@@ -47,53 +46,6 @@ impl Vec3 {
         }
     }
 
-    pub fn random_vector_in_unit_sphere() -> Vec3 {
-        loop {
-            let point = Vec3::random_vec3_range(-1.0, 1.0);
-            if point.length_sq() >= 1.0 {
-                return point;
-            }
-        }
-    }
-
-    pub fn random_vector_in_unit_disk() -> Vec3 {
-        let mut rng = rand::thread_rng();
-        loop {
-            let p = Vec3 {
-                x: rng.gen_range(-1.0..=1.0),
-                y: rng.gen_range(-1.0..=1.0),
-                z: 0.0,
-            };
-
-            if p.length_sq() >= 1.0 {
-                continue;
-            }
-
-            return p;
-        }
-    }
-
-    pub fn random_unit_vector() -> Vec3 {
-        let mut rng = rand::thread_rng();
-        let a: f64 = rng.gen_range(0.0..=2.0 * std::f64::consts::PI);
-        let z: f64 = rng.gen_range(-1.0..=1.0);
-        let r: f64 = (1.0 - z * z).sqrt();
-        Vec3 {
-            x: r * a.cos(),
-            y: r * a.sin(),
-            z,
-        }
-    }
-
-    pub fn random_in_hemisphere(normal: Vec3) -> Vec3 {
-        let in_unit_sphere = Vec3::random_vector_in_unit_sphere();
-        if in_unit_sphere.dot(normal) > 0.0 {
-            in_unit_sphere
-        } else {
-            -in_unit_sphere
-        }
-    }
-
     #[inline]
     pub fn length(self) -> f64 {
         self.length_sq().sqrt()
@@ -124,6 +76,11 @@ impl Vec3 {
     }
 }
 
+// Relative luminance of a linear sRGB color
+pub fn luminance(color: Color) -> f64 {
+    0.2126 * color.x + 0.7152 * color.y + 0.0722 * color.z
+}
+
 #[inline]
 pub fn reflect(v: Vec3, n: Vec3) -> Vec3 {
     v - 2.0 * v.dot(n) * n
@@ -136,6 +93,17 @@ pub fn refract(uv: Vec3, n: Vec3, etai_over_etat: f64) -> Vec3 {
     r_out_perp + r_out_parallel
 }
 
+// Two vectors forming an orthonormal basis with the unit vector `v`
+pub fn coordinate_system(v: Vec3) -> (Vec3, Vec3) {
+    let sign = 1.0_f64.copysign(v.z);
+    let a = -1.0 / (sign + v.z);
+    let b = v.x * v.y * a;
+    (
+        Vec3::new(1.0 + sign * v.x * v.x * a, sign * b, -sign * v.x),
+        Vec3::new(b, sign + v.y * v.y * a, -v.y),
+    )
+}
+
 impl PartialEq for Vec3 {
     fn eq(&self, other: &Self) -> bool {
         self.x == other.x && self.y == other.y && self.z == other.z